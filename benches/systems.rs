@@ -0,0 +1,90 @@
+use std::time::{Duration, Instant};
+
+use bevy::{
+    asset::AssetPlugin,
+    core::TaskPoolPlugin,
+    prelude::{App, Assets, Handle},
+    sprite::TextureAtlasSprite,
+    time::Time,
+};
+use criterion::{criterion_group, criterion_main, Criterion};
+use dynastes::{
+    bevy::{AnimationUpdateMode, BevyASM, MaybeBevyStateInstance, SpriteAnimationPlugin},
+    state_machine::{AnimationStateMachine, Condition, Marker, Transition},
+    states::index::PlaybackMode,
+};
+
+const ENTITIES: usize = 10_000;
+
+/// A character that walks with a footstep marker, and attacks whenever its trigger is set
+fn machine() -> BevyASM {
+    BevyASM(
+        AnimationStateMachine::builder()
+            .state("walk", |s| {
+                s.frames(0..=7).mspf(80).marker(Marker::frame("step", 3))
+            })
+            .state("attack", |s| {
+                s.frames(8..=13)
+                    .mspf(50)
+                    .mode(PlaybackMode::Once)
+                    .then("walk")
+            })
+            .transition(
+                Transition::new("walk".into(), "attack".into())
+                    .when(Condition::Trigger("attack".to_string())),
+            )
+            .build()
+            .unwrap(),
+    )
+}
+
+/// An app animating `ENTITIES` entities, split between two ASMs, in `mode`
+fn app(mode: AnimationUpdateMode) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        TaskPoolPlugin::default(),
+        AssetPlugin::default(),
+        SpriteAnimationPlugin,
+    ));
+    app.init_resource::<Time>();
+    app.insert_resource(mode);
+
+    let mut asms = app.world.resource_mut::<Assets<BevyASM>>();
+    let handles: [Handle<BevyASM>; 2] = [asms.add(machine()), asms.add(machine())];
+    for i in 0..ENTITIES {
+        app.world.spawn((
+            handles[i % 2].clone(),
+            TextureAtlasSprite::default(),
+            MaybeBevyStateInstance::default(),
+        ));
+    }
+    // Starts every instance, so only updates are measured
+    app.update();
+    app
+}
+
+fn systems(c: &mut Criterion) {
+    for (name, mode) in [
+        ("animation_system 10k", AnimationUpdateMode::Serial),
+        (
+            "parallel_animation_system 10k",
+            AnimationUpdateMode::Parallel,
+        ),
+    ] {
+        let mut app = app(mode);
+        let start = Instant::now();
+        let mut frame = 0;
+        c.bench_function(name, |b| {
+            b.iter(|| {
+                frame += 1;
+                app.world
+                    .resource_mut::<Time>()
+                    .update_with_instant(start + Duration::from_millis(16 * frame));
+                app.update();
+            })
+        });
+    }
+}
+
+criterion_group!(benches, systems);
+criterion_main!(benches);
@@ -0,0 +1,123 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use dynastes::{
+    state_machine::{
+        AnimationStateMachine, Condition, IndexSprite, Sprite, StateID, StateInstance, Transition,
+        UpdateArgs,
+    },
+    states::{index::IndexState, StandardData, StandardState},
+};
+
+const INSTANCES: usize = 10_000;
+
+#[derive(Debug, Default)]
+struct BenchSprite(usize);
+
+impl Sprite for BenchSprite {}
+
+impl IndexSprite for BenchSprite {
+    fn set_index(&mut self, index: usize) {
+        self.0 = index;
+    }
+
+    fn get_index(&self) -> usize {
+        self.0
+    }
+}
+
+type Machine = AnimationStateMachine<BenchSprite, StandardState<BenchSprite>, ()>;
+type Instance = StateInstance<StandardState<BenchSprite>, StandardData<BenchSprite>>;
+
+fn id(name: &str) -> StateID {
+    StateID(name.to_string())
+}
+
+/// A character that idles, walks while a parameter is set and attacks once before going back to idle
+fn machine() -> Machine {
+    let mut asm = AnimationStateMachine::with_default(
+        (),
+        id("idle"),
+        IndexState::new(0, 3, 150., None, None, None).into(),
+    );
+    asm.add_states(vec![
+        (
+            id("walk"),
+            IndexState::new(4, 11, 80., None, None, None).into(),
+        ),
+        (
+            id("attack"),
+            IndexState::new(12, 17, 50., Some(id("idle")), None, None).into(),
+        ),
+    ]);
+    asm.add_transitions(vec![
+        Transition::new(id("idle"), id("walk")).when(Condition::Bool {
+            name: "walking".to_string(),
+            value: true,
+        }),
+        Transition::new(id("walk"), id("idle")).when(Condition::Bool {
+            name: "walking".to_string(),
+            value: false,
+        }),
+        Transition::new(id("idle"), id("attack")).when(Condition::Trigger("attack".to_string())),
+    ]);
+    asm
+}
+
+/// Instances spread over every state, some of them about to transition
+fn batch(asm: &Machine) -> Vec<(Instance, BenchSprite)> {
+    (0..INSTANCES)
+        .map(|i| {
+            let mut instance = asm.try_default_instance_seeded(i as u64).unwrap();
+            match i % 3 {
+                0 => instance.parameters.set_bool("walking", true),
+                1 => instance.parameters.set_trigger("attack"),
+                _ => {}
+            }
+            (instance, BenchSprite::default())
+        })
+        .collect()
+}
+
+fn update(c: &mut Criterion) {
+    let asm = machine();
+    let args = UpdateArgs { delta_ms: 16.6 };
+
+    c.bench_function("update 10k one at a time", |b| {
+        b.iter_batched_ref(
+            || batch(&asm),
+            |batch| {
+                for (instance, sprite) in batch.iter_mut() {
+                    black_box(asm.update(instance, args, sprite));
+                }
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    c.bench_function("update 10k batched", |b| {
+        b.iter_batched_ref(
+            || batch(&asm),
+            |batch| {
+                asm.update_batch(batch, args, |i, report| {
+                    black_box((i, report.unwrap()));
+                })
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    // Every instance has long settled into its state, nothing is reported
+    let mut settled = batch(&asm);
+    for _ in 0..10 {
+        asm.update_batch(&mut settled, args, |_, _| {});
+    }
+    c.bench_function("update 10k batched, steady state", |b| {
+        b.iter(|| {
+            asm.update_batch(&mut settled, UpdateArgs { delta_ms: 1. }, |i, report| {
+                black_box((i, report.unwrap()));
+            })
+        })
+    });
+}
+
+criterion_group!(benches, update);
+criterion_main!(benches);
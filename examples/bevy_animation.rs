@@ -4,8 +4,7 @@ use dynastes::{
         BevyASM, BevyFrameSource, DynastesAnimationBundle, MaybeBevyStateInstance,
         SpriteAnimationPlugin, TextureAtlasGridMetadata,
     },
-    state_machine::StateID,
-    states::index::IndexState,
+    state_machine::AnimationStateMachine,
 };
 
 const COMMON_MSPF: f64 = 1000. / 8.;
@@ -30,16 +29,16 @@ fn setup_animations(
 ) {
     commands.spawn(Camera2dBundle::default());
 
-    let fs = BevyFrameSource {
-        path: "sprite-sheet.png".into(),
-        metadata: TextureAtlasGridMetadata {
+    let fs = BevyFrameSource::new(
+        "sprite-sheet.png",
+        TextureAtlasGridMetadata {
             tile_size: [128., 128.].into(),
             columns: 26,
             rows: 2,
             padding: None,
             offset: None,
         },
-    };
+    );
 
     let texture_handle = asset_server.load(fs.path.clone());
     let texture_atlas = TextureAtlas::from_grid(
@@ -57,14 +56,14 @@ fn setup_animations(
         &mut state_machines,
         texture_atlas_handle.clone(),
         (-60., 0., 0.).into(),
-        None,
+        1.,
     );
     walk_animation_with_fluidity(
         &mut commands,
         &mut state_machines,
         texture_atlas_handle.clone(),
         (60., 0., 0.).into(),
-        Some(1. / 2.),
+        1. / 2.,
     );
 }
 
@@ -73,20 +72,26 @@ fn walk_animation_with_fluidity(
     state_machines: &mut ResMut<Assets<BevyASM>>,
     texture_atlas_handle: Handle<TextureAtlas>,
     position: Vec3,
-    fluidity: Option<f64>,
+    fluidity: f64,
 ) {
-    let walk_id: StateID = "walk".to_string().into();
-    let idle_id: StateID = "idle".to_string().into();
-
-    let walk_state: IndexState<TextureAtlasSprite> =
-        IndexState::new(0, 9, COMMON_MSPF, Some(idle_id.clone()), None, fluidity);
-    let idle_state: IndexState<TextureAtlasSprite> =
-        IndexState::new(26, 51, COMMON_MSPF, Some(walk_id.clone()), None, fluidity);
-
-    let mut asm = BevyASM::new(texture_atlas_handle.clone(), idle_id, idle_state);
-    asm.0.add_states(vec![(walk_id, walk_state)]);
+    let asm = AnimationStateMachine::builder()
+        .frame_source(texture_atlas_handle.clone())
+        .state("idle", |s| {
+            s.frames(26..=51)
+                .mspf(COMMON_MSPF)
+                .fluidity(fluidity)
+                .then("walk")
+        })
+        .state("walk", |s| {
+            s.frames(0..=9)
+                .mspf(COMMON_MSPF)
+                .fluidity(fluidity)
+                .then("idle")
+        })
+        .build()
+        .expect("the example's state machine is valid");
 
-    let asm_handle = state_machines.add(asm);
+    let asm_handle = state_machines.add(BevyASM(asm));
     let scale = 4.;
 
     commands.spawn(DynastesAnimationBundle {
@@ -0,0 +1,246 @@
+use std::fmt;
+
+use serde::{
+    de::{MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+
+use crate::{
+    error::{Error, Result},
+    state_machine::{AnimationStateMachine, IndexSprite, Sprite, StateID},
+    states::{
+        frame_list::{Frame, FrameListState},
+        StandardState,
+    },
+};
+
+/// The ID given to the only state of a sheet that has no tags
+pub const UNTAGGED_STATE_ID: &str = "default";
+
+#[derive(Debug, Clone, Deserialize)]
+/// A sprite sheet and its metadata as exported by Aseprite's "Export Sprite Sheet" as JSON.
+///
+/// Both the "Array" and "Hash" layouts of the `frames` field are supported.
+pub struct AsepriteSheet {
+    /// Every frame of the sheet, in the order they were exported
+    #[serde(deserialize_with = "deserialize_frames")]
+    pub frames: Vec<AsepriteFrame>,
+    /// Information about the sheet as a whole
+    pub meta: AsepriteMeta,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+/// A single frame of an `AsepriteSheet`
+pub struct AsepriteFrame {
+    /// Where the frame is located in the sheet's image
+    pub frame: AsepriteRect,
+    /// The number of milliseconds the frame stays on screen for
+    pub duration: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+/// A rectangle in pixels, with its origin at the top left of the image
+pub struct AsepriteRect {
+    /// The left edge of the rectangle
+    pub x: u32,
+    /// The top edge of the rectangle
+    pub y: u32,
+    /// The width of the rectangle
+    pub w: u32,
+    /// The height of the rectangle
+    pub h: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+/// The dimensions of an image in pixels
+pub struct AsepriteSize {
+    /// The width of the image
+    pub w: u32,
+    /// The height of the image
+    pub h: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+/// Information about an `AsepriteSheet` as a whole
+pub struct AsepriteMeta {
+    /// The path of the sheet's image, relative to the JSON file
+    pub image: String,
+    /// The dimensions of the sheet's image
+    pub size: AsepriteSize,
+    /// The animations of the sheet
+    #[serde(default, rename = "frameTags")]
+    pub frame_tags: Vec<AsepriteTag>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+/// A named range of frames, which becomes one state of the imported state machine
+pub struct AsepriteTag {
+    /// The name of the tag, used as the state's ID
+    pub name: String,
+    /// The first frame of the tag (inclusive)
+    pub from: usize,
+    /// The last frame of the tag (inclusive)
+    pub to: usize,
+    /// The order in which the tag's frames are played
+    #[serde(default)]
+    pub direction: AsepriteDirection,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// The order in which a tag's frames are played
+pub enum AsepriteDirection {
+    /// From `from` to `to`
+    #[default]
+    Forward,
+    /// From `to` to `from`
+    Reverse,
+    /// From `from` to `to` and back again
+    Pingpong,
+    /// From `to` to `from` and back again
+    PingpongReverse,
+}
+
+impl AsepriteSheet {
+    /// Parses a sheet from the contents of an Aseprite JSON file
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|error| Error::Import(error.to_string()))
+    }
+
+    /// The frames played by `tag`, in order, honoring its direction
+    pub fn tag_frames(&self, tag: &AsepriteTag) -> Result<Vec<Frame>> {
+        if tag.from > tag.to || tag.to >= self.frames.len() {
+            return Err(Error::Import(format!(
+                "tag \"{}\" uses frames {}..={}, but the sheet only has {} frames",
+                tag.name,
+                tag.from,
+                tag.to,
+                self.frames.len()
+            )));
+        }
+
+        let forward: Vec<usize> = (tag.from..=tag.to).collect();
+        let inner = forward
+            .get(1..forward.len() - 1)
+            .unwrap_or_default()
+            .iter()
+            .copied();
+        let indices: Vec<usize> = match tag.direction {
+            AsepriteDirection::Forward => forward,
+            AsepriteDirection::Reverse => forward.into_iter().rev().collect(),
+            // The end frames are not repeated when turning around, just like in Aseprite
+            AsepriteDirection::Pingpong => forward.iter().copied().chain(inner.rev()).collect(),
+            AsepriteDirection::PingpongReverse => {
+                forward.iter().rev().copied().chain(inner).collect()
+            }
+        };
+
+        Ok(indices
+            .into_iter()
+            .map(|index| Frame {
+                index,
+                duration_ms: self.frames[index].duration,
+            })
+            .collect())
+    }
+
+    /// One looping state per tag, or a single state named `UNTAGGED_STATE_ID` playing every frame if
+    /// the sheet has no tags.
+    pub fn states<S>(&self) -> Result<Vec<(StateID, FrameListState<S>)>> {
+        if self.meta.frame_tags.is_empty() {
+            let frames = self
+                .frames
+                .iter()
+                .enumerate()
+                .map(|(index, frame)| Frame {
+                    index,
+                    duration_ms: frame.duration,
+                })
+                .collect::<Vec<_>>();
+            return Ok(vec![(
+                StateID(UNTAGGED_STATE_ID.to_string()),
+                FrameListState::new(frames, None),
+            )]);
+        }
+
+        self.meta
+            .frame_tags
+            .iter()
+            .map(|tag| {
+                Ok((
+                    StateID(tag.name.clone()),
+                    FrameListState::new(self.tag_frames(tag)?, None),
+                ))
+            })
+            .collect()
+    }
+
+    /// The ID of the first state, which is used as the default state
+    pub fn default_id(&self) -> StateID {
+        self.meta
+            .frame_tags
+            .first()
+            .map(|tag| StateID(tag.name.clone()))
+            .unwrap_or_else(|| StateID(UNTAGGED_STATE_ID.to_string()))
+    }
+
+    /// Creates a state machine with a state for every tag, see `states`
+    pub fn state_machine<S, F>(
+        &self,
+        frame_source: F,
+    ) -> Result<AnimationStateMachine<S, StandardState<S>, F>>
+    where
+        S: Send + Sync + Sprite + IndexSprite,
+    {
+        let states = self
+            .states()?
+            .into_iter()
+            .map(|(id, state)| (id, state.into()))
+            .collect();
+        Ok(AnimationStateMachine::with_states(
+            frame_source,
+            self.default_id(),
+            states,
+        ))
+    }
+}
+
+/// Reads the frames from either an array or a map keyed by file name, keeping the exported order
+fn deserialize_frames<'de, D>(deserializer: D) -> std::result::Result<Vec<AsepriteFrame>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct FramesVisitor;
+
+    impl<'de> Visitor<'de> for FramesVisitor {
+        type Value = Vec<AsepriteFrame>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an array or map of frames")
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> std::result::Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let mut frames = Vec::with_capacity(seq.size_hint().unwrap_or_default());
+            while let Some(frame) = seq.next_element()? {
+                frames.push(frame);
+            }
+            Ok(frames)
+        }
+
+        fn visit_map<A>(self, mut map: A) -> std::result::Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            let mut frames = Vec::with_capacity(map.size_hint().unwrap_or_default());
+            while let Some((_, frame)) = map.next_entry::<String, _>()? {
+                frames.push(frame);
+            }
+            Ok(frames)
+        }
+    }
+
+    deserializer.deserialize_any(FramesVisitor)
+}
@@ -1,18 +1,30 @@
-use bevy::sprite::TextureAtlasSprite;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    state_machine::{StateContainer, StateID},
-    states::index::IndexState,
+    bevy::BevyState,
+    state_machine::{AnyStateTransition, StateContainer, StateGroup, StateID, Transition},
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-/// A helper struct for serializing and deserializing `BevyASM`
+/// A helper struct for serializing and deserializing `BevyASM`.
+///
+/// Older `.asm` documents are read with `migration::asm_from_ron`, which upgrades them to this format.
 pub struct BevyASMSerde {
+    /// The version of the `.asm` format, see `migration::ASM_VERSION`
+    pub version: u32,
     /// The path to the TextureAtlas's `.fs` file
     pub frame_source: String,
     /// The default StateID for new instances
     pub default_id: StateID,
     /// The map of States and StateIDs for the ASM
-    pub states: StateContainer<IndexState<TextureAtlasSprite>>,
+    pub states: StateContainer<BevyState>,
+    /// The map of StateGroups and their IDs, nesting the ASM's states
+    #[serde(default)]
+    pub groups: StateContainer<StateGroup>,
+    /// The conditional transitions between the ASM's states
+    #[serde(default)]
+    pub transitions: Vec<Transition>,
+    /// The transitions that can be taken from any of the ASM's states
+    #[serde(default)]
+    pub any_state_transitions: Vec<AnyStateTransition>,
 }
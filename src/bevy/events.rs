@@ -0,0 +1,50 @@
+use bevy::prelude::{Entity, Event};
+
+use crate::state_machine::{AlreadyPlaying, StateID, TransitionReason};
+
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+/// Sent by the `animation_system` for every marker an entity's animation crossed
+pub struct AnimationMarker {
+    /// The entity whose animation crossed the marker
+    pub entity: Entity,
+    /// The state the marker belongs to
+    pub state: StateID,
+    /// The name of the marker
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+/// Sent by the `animation_system` for every state change of an entity's animation
+pub struct StateChanged {
+    /// The entity whose animation changed state
+    pub entity: Entity,
+    /// The state that was left
+    pub from: StateID,
+    /// The state that was entered
+    pub to: StateID,
+    /// Why the state changed
+    pub reason: TransitionReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Event)]
+/// Send to change the state of an entity's animation from gameplay code, consumed by the `animation_command_system`
+pub enum AnimationCommand {
+    /// Immediately play `state`, see `AnimationStateMachine::play`
+    Play {
+        /// The entity whose animation should change
+        entity: Entity,
+        /// The state to play
+        state: StateID,
+        /// What to do if the entity is already playing `state`
+        already_playing: AlreadyPlaying,
+    },
+    /// Play `state` once the current state reaches its end, see `AnimationStateMachine::play_queued`
+    PlayQueued {
+        /// The entity whose animation should change
+        entity: Entity,
+        /// The state to play
+        state: StateID,
+        /// What to do if the entity is already playing `state`
+        already_playing: AlreadyPlaying,
+    },
+}
@@ -1,10 +1,17 @@
+#[cfg(feature = "aseprite")]
+use std::path::Path;
+
 use bevy::{
     asset::{AssetLoader, LoadContext, LoadedAsset},
+    log::warn,
     prelude::{FromWorld, World},
     utils::BoxedFuture,
 };
 
-use crate::bevy::{bevy_serde::BevyASMSerde, BevyASM, BevyFrameSource};
+use crate::bevy::{
+    migration::{asm_from_ron, frame_source_from_ron},
+    BevyASM,
+};
 
 /// Loads `BevyASM`s using a serialized `.asm` file
 pub struct AsmLoader;
@@ -27,9 +34,18 @@ impl AssetLoader for AsmLoader {
     ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
         Box::pin(async move {
             let string = std::str::from_utf8(bytes)?;
-            let asm_serde: BevyASMSerde = ron::from_str(string)?;
+            let asm_serde = asm_from_ron(string)?;
+
+            let frame_source_bytes = load_context
+                .read_asset_bytes(&asm_serde.frame_source)
+                .await?;
+            let frame_source = frame_source_from_ron(std::str::from_utf8(&frame_source_bytes)?)?;
 
             let asm = BevyASM::with_context(asm_serde.clone(), load_context);
+            let warnings = asm.0.validate(Some(frame_source.metadata.frame_count()))?;
+            for warning in warnings {
+                warn!("{}: {warning}", load_context.path().display());
+            }
 
             load_context.set_default_asset(
                 LoadedAsset::new(asm).with_dependency(asm_serde.frame_source.into()),
@@ -54,7 +70,7 @@ impl AssetLoader for FrameSourceLoader {
     ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
         Box::pin(async move {
             let string = std::str::from_utf8(bytes)?;
-            let frame_source: BevyFrameSource = ron::from_str(string)?;
+            let frame_source = frame_source_from_ron(string)?;
             let texture_atlas = frame_source.with_context(load_context);
             load_context.set_default_asset(
                 LoadedAsset::new(texture_atlas).with_dependency(frame_source.path.into()),
@@ -69,3 +85,55 @@ impl FromWorld for FrameSourceLoader {
         Self
     }
 }
+
+/// Loads `BevyASM`s from the JSON sprite sheet data exported by Aseprite.
+///
+/// Each tag becomes a state, the texture atlas is available under the `atlas` label.
+#[cfg(feature = "aseprite")]
+pub struct AsepriteLoader;
+
+#[cfg(feature = "aseprite")]
+impl AssetLoader for AsepriteLoader {
+    fn extensions(&self) -> &[&str] {
+        &["aseprite.json"]
+    }
+
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let string = std::str::from_utf8(bytes)?;
+            let sheet = crate::aseprite::AsepriteSheet::from_json(string)?;
+
+            // The image path is relative to the JSON file
+            let image_path = load_context
+                .path()
+                .parent()
+                .unwrap_or_else(|| Path::new(""))
+                .join(&sheet.meta.image);
+            let texture_atlas = sheet.texture_atlas(
+                load_context.get_handle(bevy::asset::AssetPath::from(image_path.as_path())),
+            );
+            let frame_source = load_context.set_labeled_asset(
+                "atlas",
+                LoadedAsset::new(texture_atlas).with_dependency(image_path.into()),
+            );
+
+            let asm = BevyASM(sheet.state_machine(frame_source)?);
+            // Tags don't link to each other, so every state but the default is expected to be unreachable
+            asm.0.validate(Some(sheet.frames.len()))?;
+
+            load_context.set_default_asset(LoadedAsset::new(asm));
+            Ok(())
+        })
+    }
+}
+
+#[cfg(feature = "aseprite")]
+impl FromWorld for AsepriteLoader {
+    fn from_world(_world: &mut World) -> Self {
+        Self
+    }
+}
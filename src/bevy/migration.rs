@@ -0,0 +1,240 @@
+use std::path::PathBuf;
+
+use bevy::sprite::TextureAtlasSprite;
+use serde::{Deserialize, Deserializer};
+
+use crate::{
+    bevy::{bevy_serde::BevyASMSerde, BevyFrameSource, BevyState, TextureAtlasGridMetadata},
+    error::{Error, Result, ValidationError},
+    state_machine::{AnyStateTransition, Marker, StateContainer, StateGroup, StateID, Transition},
+    states::{
+        frame_list::FrameListState,
+        index::{IndexState, PlaybackMode},
+        random_choice::RandomChoiceState,
+    },
+};
+
+/// The version of the `.asm` format written by this version of the crate.
+///
+/// Documents without a version were written before it was recorded, they are also read when the
+/// version is given explicitly:
+/// * 0: every state is an `IndexState`, written without the `Index` tag
+/// * 1: states are tagged by kind, and index states store their derived frame durations
+pub const ASM_VERSION: u32 = 2;
+
+/// The version of the `.fs` format written by this version of the crate, documents without a version
+/// are version 0
+pub const FRAME_SOURCE_VERSION: u32 = 1;
+
+/// Reads an `.asm` document of any known version, upgrading it to the current format
+pub fn asm_from_ron(source: &str) -> Result<BevyASMSerde> {
+    let probe: AsmVersion = parse(source)?;
+    match probe.version {
+        Some(ASM_VERSION) => parse(source),
+        Some(1) => parse::<AsmV1>(source)?.upgrade(),
+        Some(0) => parse::<AsmV0>(source)?.upgrade().upgrade(),
+        // Versions 0 and 1 are told apart by whether their states are tagged
+        None => match parse::<AsmV1>(source) {
+            Ok(v1) => v1.upgrade(),
+            Err(error) => match parse::<AsmV0>(source) {
+                Ok(v0) => v0.upgrade().upgrade(),
+                // The later format is the more likely one to have been meant
+                Err(_) => Err(error),
+            },
+        },
+        Some(found) => Err(Error::UnsupportedVersion {
+            found,
+            latest: ASM_VERSION,
+        }),
+    }
+}
+
+/// Reads an `.fs` document of any known version, upgrading it to the current format
+pub fn frame_source_from_ron(source: &str) -> Result<BevyFrameSource> {
+    let probe: FrameSourceVersion = parse(source)?;
+    match probe.version {
+        Some(FRAME_SOURCE_VERSION) => parse(source),
+        Some(0) | None => parse::<FrameSourceV0>(source).map(FrameSourceV0::upgrade),
+        Some(found) => Err(Error::UnsupportedVersion {
+            found,
+            latest: FRAME_SOURCE_VERSION,
+        }),
+    }
+}
+
+fn parse<'a, T: Deserialize<'a>>(source: &'a str) -> Result<T> {
+    ron::from_str(source).map_err(|error| Error::Import(error.to_string()))
+}
+
+/// Reads an optional field that is written as a plain value rather than `Some(value)`
+fn deserialize_some<'de, D, T>(deserializer: D) -> std::result::Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "BevyASMSerde")]
+/// Only the version of an `.asm` document, ignoring the rest
+struct AsmVersion {
+    #[serde(default, deserialize_with = "deserialize_some")]
+    version: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "BevyFrameSource")]
+/// Only the version of an `.fs` document, ignoring the rest
+struct FrameSourceVersion {
+    #[serde(default, deserialize_with = "deserialize_some")]
+    version: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "BevyASMSerde")]
+/// An `.asm` document from before states were tagged by kind
+struct AsmV0 {
+    frame_source: String,
+    default_id: StateID,
+    states: StateContainer<IndexStateV1>,
+}
+
+impl AsmV0 {
+    fn upgrade(self) -> AsmV1 {
+        AsmV1 {
+            frame_source: self.frame_source,
+            default_id: self.default_id,
+            states: self
+                .states
+                .into_iter()
+                .map(|(id, state)| (id, StateV1::Index(state)))
+                .collect(),
+            groups: StateContainer::default(),
+            transitions: Vec::new(),
+            any_state_transitions: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "BevyASMSerde")]
+/// An `.asm` document from before the version was recorded
+struct AsmV1 {
+    frame_source: String,
+    default_id: StateID,
+    states: StateContainer<StateV1>,
+    #[serde(default)]
+    groups: StateContainer<StateGroup>,
+    #[serde(default)]
+    transitions: Vec<Transition>,
+    #[serde(default)]
+    any_state_transitions: Vec<AnyStateTransition>,
+}
+
+impl AsmV1 {
+    fn upgrade(self) -> Result<BevyASMSerde> {
+        let mut problems = Vec::new();
+        let mut states = StateContainer::default();
+        for (id, state) in self.states {
+            let state: BevyState = match state {
+                StateV1::Index(state) => match state.upgrade(&id) {
+                    Ok(state) => state.into(),
+                    Err(problem) => {
+                        problems.push(problem);
+                        continue;
+                    }
+                },
+                StateV1::FrameList(state) => state.into(),
+                StateV1::RandomChoice(state) => state.into(),
+            };
+            states.insert(id, state);
+        }
+        if !problems.is_empty() {
+            return Err(Error::Invalid(problems));
+        }
+
+        Ok(BevyASMSerde {
+            version: ASM_VERSION,
+            frame_source: self.frame_source,
+            default_id: self.default_id,
+            states,
+            groups: self.groups,
+            transitions: self.transitions,
+            any_state_transitions: self.any_state_transitions,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+/// A state in a version 1 `.asm` document
+enum StateV1 {
+    Index(IndexStateV1),
+    FrameList(FrameListState<TextureAtlasSprite>),
+    RandomChoice(RandomChoiceState<TextureAtlasSprite>),
+}
+
+#[derive(Deserialize)]
+/// An `IndexState` from before its frame durations were derived from a `FrameRate`.
+///
+/// The derived `actual_mspf` and `frames_per_increment` were written too, they are ignored in favour
+/// of recomputing them from `nominal_mspf` and `fluidity`.
+struct IndexStateV1 {
+    min_i: usize,
+    max_i: usize,
+    nominal_mspf: f64,
+    next_state: Option<StateID>,
+    #[serde(default)]
+    mode: PlaybackMode,
+    #[serde(default = "default_loops")]
+    loops: usize,
+    phase: f64,
+    #[serde(default)]
+    random_phase: Option<(f64, f64)>,
+    fluidity: f64,
+    #[serde(default)]
+    markers: Vec<Marker>,
+}
+
+impl IndexStateV1 {
+    fn upgrade(
+        self,
+        id: &StateID,
+    ) -> std::result::Result<IndexState<TextureAtlasSprite>, ValidationError> {
+        let mut builder = IndexState::builder()
+            .frames(self.min_i..=self.max_i)
+            .mspf(self.nominal_mspf)
+            .mode(self.mode)
+            .loops(self.loops)
+            .phase(self.phase)
+            .fluidity(self.fluidity);
+        if let Some(next_state) = self.next_state {
+            builder = builder.then(next_state);
+        }
+        if let Some((min_ms, max_ms)) = self.random_phase {
+            builder = builder.random_phase(min_ms, max_ms);
+        }
+        for marker in self.markers {
+            builder = builder.marker(marker);
+        }
+        builder.build(id)
+    }
+}
+
+fn default_loops() -> usize {
+    1
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "BevyFrameSource")]
+/// An `.fs` document from before the version was recorded
+struct FrameSourceV0 {
+    path: PathBuf,
+    metadata: TextureAtlasGridMetadata,
+}
+
+impl FrameSourceV0 {
+    fn upgrade(self) -> BevyFrameSource {
+        BevyFrameSource::new(self.path, self.metadata)
+    }
+}
@@ -2,26 +2,44 @@ use std::path::PathBuf;
 
 use bevy::{
     asset::LoadContext,
-    prelude::{AssetServer, Bundle, Component, Handle, Image, Res, Vec2},
+    prelude::{AssetServer, Bundle, Component, Entity, Handle, Image, Res, Vec2},
     reflect::{TypePath, TypeUuid},
     sprite::{SpriteSheetBundle, TextureAtlas, TextureAtlasSprite},
 };
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    state_machine::{AnimationStateMachine, IndexSprite, Sprite, StateID, StateInstance},
-    states::index::{IndexData, IndexState},
+    error::Result,
+    state_machine::{
+        AnimationStateMachine, IndexSprite, LayerOptions, Sprite, StateID, StateInstance,
+    },
+    states::{StandardData, StandardState},
 };
 
 /// Serde helper structs for the bevy plugin
 pub mod bevy_serde;
+mod events;
 /// Asset loaders for the bevy plugin
 pub mod loader;
+/// Versioning of the `.asm` and `.fs` formats, and upgrades from older versions
+pub mod migration;
 mod plugin;
+mod snapshot;
+mod sync;
+
+pub use events::{AnimationCommand, AnimationMarker, StateChanged};
+pub use plugin::{
+    animation_command_system, animation_system, crossfade_system, layer_system,
+    parallel_animation_system, sync_group_system, AnimationTimeScale, AnimationUpdateMode,
+    SpriteAnimationPlugin,
+};
+pub use snapshot::{AnimationSnapshot, EntitySnapshot};
+pub use sync::{SyncGroupMember, SyncGroups};
 
-pub use plugin::SpriteAnimationPlugin;
-
-use self::bevy_serde::BevyASMSerde;
+use self::{
+    bevy_serde::BevyASMSerde,
+    migration::{ASM_VERSION, FRAME_SOURCE_VERSION},
+};
 
 #[derive(Bundle)]
 /// A Bundle of the components needed to run an animation with Bevy ECS
@@ -34,40 +52,62 @@ pub struct DynastesAnimationBundle {
     pub sprite_sheet: SpriteSheetBundle,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+/// Turns a sprite sheet into the overlay showing the outgoing state of `target`'s crossfades.
+///
+/// The overlay is hidden unless `target` is crossfading, and its alpha ramps down as the crossfade
+/// progresses. It should be drawn above `target`, e.g. as a child with a higher z.
+pub struct CrossfadeOverlay {
+    /// The animated entity whose crossfades are shown
+    pub target: Entity,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+/// Turns an animated entity into a layer of `base`, e.g. the arms on top of a character's body.
+///
+/// Layers are updated by the `layer_system` after their base, which must not be a layer itself.
+pub struct AnimationLayer {
+    /// The entity animating the base layer
+    pub base: Entity,
+    /// How the layer is kept in step with `base`
+    pub options: LayerOptions,
+}
+
+/// The states that a `BevyASM` can be made of
+pub type BevyState = StandardState<TextureAtlasSprite>;
+
 /// A convenience wrapper for the bevy monomorphization of the ASM
 #[derive(Debug, Component, TypeUuid, TypePath)]
 #[uuid = "74377e21-153d-4e30-9b5e-1b857a9ab807"]
-pub struct BevyASM(
-    pub  AnimationStateMachine<
-        TextureAtlasSprite,
-        IndexState<TextureAtlasSprite>,
-        Handle<TextureAtlas>,
-    >,
-);
+pub struct BevyASM(pub AnimationStateMachine<TextureAtlasSprite, BevyState, Handle<TextureAtlas>>);
 
 impl BevyASM {
     /// Creates a new Bevy ASM initialized with `default_id` and `default_state`
     pub fn new(
         frame_source: Handle<TextureAtlas>,
         default_id: StateID,
-        default_state: IndexState<TextureAtlasSprite>,
+        default_state: impl Into<BevyState>,
     ) -> Self {
         BevyASM(AnimationStateMachine::with_default(
             frame_source,
             default_id,
-            default_state,
+            default_state.into(),
         ))
     }
 
     /// Creates a new Bevy ASM initialized with `default_id` and `default_state`
-    pub fn with_context<'a>(asm_serde: BevyASMSerde, load_context: &'a mut LoadContext) -> Self {
+    pub fn with_context(asm_serde: BevyASMSerde, load_context: &mut LoadContext) -> Self {
         let frame_source = load_context.get_handle::<_, TextureAtlas>(&asm_serde.frame_source);
 
-        BevyASM(AnimationStateMachine::with_states(
+        let mut asm = AnimationStateMachine::with_states(
             frame_source,
             asm_serde.default_id,
             asm_serde.states,
-        ))
+        );
+        asm.add_groups(asm_serde.groups.into_iter().collect());
+        asm.add_transitions(asm_serde.transitions);
+        asm.add_any_state_transitions(asm_serde.any_state_transitions);
+        BevyASM(asm)
     }
 
     /// Creates a new instance from the default state
@@ -75,16 +115,27 @@ impl BevyASM {
         BevyStateInstance(self.0.default_instance())
     }
 
+    /// Creates a new instance from the default state
+    pub fn try_default_instance(&self) -> Result<BevyStateInstance> {
+        self.0.try_default_instance().map(BevyStateInstance)
+    }
+
+    /// Creates a new instance from the default state whose random choices are determined by `seed`
+    pub fn try_default_instance_seeded(&self, seed: u64) -> Result<BevyStateInstance> {
+        self.0
+            .try_default_instance_seeded(seed)
+            .map(BevyStateInstance)
+    }
+
     /// Creates a new instance from the given state id if it exists
     pub fn new_instance(&self, instance_id: StateID) -> Option<BevyStateInstance> {
-        self.0
-            .new_instance(instance_id)
-            .map(|i| BevyStateInstance(i))
+        self.0.new_instance(instance_id).map(BevyStateInstance)
     }
 
     /// Converts the Bevy-safe struct into a serializable struct with the help of the given AssetServer
     pub fn serialize_with_server(&self, server: Res<AssetServer>) -> Option<BevyASMSerde> {
         Some(BevyASMSerde {
+            version: ASM_VERSION,
             frame_source: server
                 .get_handle_path(self.0.frame_source())?
                 .path()
@@ -92,18 +143,19 @@ impl BevyASM {
                 .to_string(),
             default_id: self.0.default_id().to_owned(),
             states: self.0.states().to_owned(),
+            groups: self.0.groups().to_owned(),
+            transitions: self.0.transitions().to_vec(),
+            any_state_transitions: self.0.any_state_transitions().to_vec(),
         })
     }
 }
 
 /// A convenience wrapper monomorphizing the `StateInstance` for the BevyASM
-#[derive(Debug, Serialize, Deserialize, Component, TypePath)]
-pub struct BevyStateInstance(
-    pub StateInstance<IndexState<TextureAtlasSprite>, IndexData<TextureAtlasSprite>>,
-);
+#[derive(Debug, Clone, Serialize, Deserialize, Component, TypePath)]
+pub struct BevyStateInstance(pub StateInstance<BevyState, StandardData<TextureAtlasSprite>>);
 
 /// A convenience wrapper for an optional `BevyStateInstance`
-#[derive(Debug, Serialize, Deserialize, Component, TypePath, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Component, TypePath, Default)]
 pub struct MaybeBevyStateInstance(pub Option<BevyStateInstance>);
 
 impl Sprite for TextureAtlasSprite {}
@@ -120,8 +172,13 @@ impl IndexSprite for TextureAtlasSprite {
 
 #[derive(Debug, Clone, Serialize, Deserialize, TypePath, TypeUuid)]
 #[uuid = "73b8df5e-c12d-4830-83c8-faec6ee4e18d"]
-/// The source of the `BevyASM`'s frame
+/// The source of the `BevyASM`'s frame.
+///
+/// Older `.fs` documents are read with `migration::frame_source_from_ron`, which upgrades them to this
+/// format.
 pub struct BevyFrameSource {
+    /// The version of the `.fs` format, see `migration::FRAME_SOURCE_VERSION`
+    pub version: u32,
     /// The asset path to the ASM's sprite sheet
     pub path: PathBuf,
     /// Metadata for constructing the texture atlas from the sprite sheet
@@ -129,8 +186,17 @@ pub struct BevyFrameSource {
 }
 
 impl BevyFrameSource {
+    /// Creates a frame source in the current version of the format
+    pub fn new(path: impl Into<PathBuf>, metadata: TextureAtlasGridMetadata) -> Self {
+        Self {
+            version: FRAME_SOURCE_VERSION,
+            path: path.into(),
+            metadata,
+        }
+    }
+
     /// Loads the referenced sprite sheet and converts it to a texture atlas
-    pub fn with_context<'a>(&self, load_context: &'a mut LoadContext) -> TextureAtlas {
+    pub fn with_context(&self, load_context: &mut LoadContext) -> TextureAtlas {
         let handle = load_context.get_handle::<_, Image>(self.path.to_str().unwrap());
         TextureAtlas::from_grid(
             handle,
@@ -157,3 +223,29 @@ pub struct TextureAtlasGridMetadata {
     /// Where the grid starts relative to the top left corner
     pub offset: Option<Vec2>,
 }
+
+#[cfg(feature = "aseprite")]
+impl crate::aseprite::AsepriteSheet {
+    /// Converts the sheet's frames into a texture atlas over `texture`, the sheet's image
+    pub fn texture_atlas(&self, texture: Handle<Image>) -> TextureAtlas {
+        let size = Vec2::new(self.meta.size.w as f32, self.meta.size.h as f32);
+        let mut atlas = TextureAtlas::new_empty(texture, size);
+        for frame in &self.frames {
+            let rect = frame.frame;
+            atlas.add_texture(bevy::math::Rect::new(
+                rect.x as f32,
+                rect.y as f32,
+                (rect.x + rect.w) as f32,
+                (rect.y + rect.h) as f32,
+            ));
+        }
+        atlas
+    }
+}
+
+impl TextureAtlasGridMetadata {
+    /// The number of frames in the grid
+    pub fn frame_count(&self) -> usize {
+        self.columns * self.rows
+    }
+}
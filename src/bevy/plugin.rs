@@ -1,15 +1,24 @@
+use std::sync::{Mutex, PoisonError};
+
 use bevy::{
-    prelude::{AddAsset, App, Assets, Handle, Plugin, Query, Res, Update},
-    sprite::TextureAtlasSprite,
+    asset::HandleId,
+    ecs::{query::BatchingStrategy, schedule::common_conditions::resource_equals},
+    log::error,
+    prelude::{
+        AddAsset, App, Assets, Entity, EventReader, EventWriter, Handle, IntoSystemConfigs, Local,
+        Plugin, Query, Res, ResMut, Resource, Update, Visibility, Without,
+    },
+    sprite::{TextureAtlas, TextureAtlasSprite},
     time::Time,
 };
 
 use crate::{
     bevy::{
         loader::{AsmLoader, FrameSourceLoader},
-        BevyASM, BevyFrameSource, MaybeBevyStateInstance,
+        AnimationCommand, AnimationLayer, AnimationMarker, BevyASM, BevyFrameSource,
+        CrossfadeOverlay, MaybeBevyStateInstance, StateChanged, SyncGroupMember, SyncGroups,
     },
-    state_machine::UpdateArgs,
+    state_machine::{UpdateArgs, UpdateReport},
 };
 
 /// The Dynastes sprite animation plugin for Bevy.
@@ -24,29 +33,411 @@ impl Plugin for SpriteAnimationPlugin {
         app.init_asset_loader::<AsmLoader>();
         app.add_asset::<BevyFrameSource>();
         app.init_asset_loader::<FrameSourceLoader>();
-        app.add_systems(Update, animation_system);
+        #[cfg(feature = "aseprite")]
+        app.init_asset_loader::<crate::bevy::loader::AsepriteLoader>();
+        app.add_event::<AnimationMarker>();
+        app.add_event::<StateChanged>();
+        app.add_event::<AnimationCommand>();
+        app.init_resource::<AnimationTimeScale>();
+        app.init_resource::<SyncGroups>();
+        app.init_resource::<AnimationUpdateMode>();
+        app.add_systems(
+            Update,
+            (
+                animation_command_system,
+                animation_system.run_if(resource_equals(AnimationUpdateMode::Serial)),
+                parallel_animation_system.run_if(resource_equals(AnimationUpdateMode::Parallel)),
+                layer_system,
+                sync_group_system,
+                crossfade_system,
+            )
+                .chain(),
+        );
     }
 }
 
-/// Run the animations across bundles of `BevyASM` and `BevyStateInstance`
+#[derive(Debug, Clone, Copy, PartialEq, Resource)]
+/// Scales the time passed to every animation, on top of each instance's own speed.
+///
+/// Set to 0 to freeze every animation, e.g. for hit-stop, or below 1 for bullet-time.
+pub struct AnimationTimeScale(pub f64);
+
+impl AnimationTimeScale {
+    /// The factor the systems scale time by, negative and non-finite scales freeze every animation
+    pub fn factor(&self) -> f64 {
+        if self.0.is_finite() {
+            self.0.max(0.)
+        } else {
+            0.
+        }
+    }
+}
+
+impl Default for AnimationTimeScale {
+    fn default() -> Self {
+        AnimationTimeScale(1.)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Resource)]
+/// How the `SpriteAnimationPlugin` runs the animations of entities that aren't layers
+pub enum AnimationUpdateMode {
+    /// One entity after another, with the `animation_system`
+    #[default]
+    Serial,
+    /// Spread over the compute task pool, with the `parallel_animation_system`.
+    /// Worth it once there are thousands of animated entities, `benches/systems.rs` compares the two.
+    Parallel,
+}
+
+/// The entities animated on their own, rather than by the `layer_system` or `sync_group_system`
+type Standalone = (Without<AnimationLayer>, Without<SyncGroupMember>);
+
+/// Run the animations across bundles of `BevyASM` and `BevyStateInstance`, except for the
+/// `SyncGroupMember`s which follow their group's clock instead
+///
+/// Sends an `AnimationMarker` event for every marker crossed and a `StateChanged` event for every transition.
 pub fn animation_system(
     time: Res<Time>,
+    time_scale: Res<AnimationTimeScale>,
+    asms: Res<Assets<BevyASM>>,
+    mut query: Query<
+        (
+            Entity,
+            &Handle<BevyASM>,
+            &mut TextureAtlasSprite,
+            &mut MaybeBevyStateInstance,
+        ),
+        Standalone,
+    >,
+    mut markers: EventWriter<AnimationMarker>,
+    mut state_changes: EventWriter<StateChanged>,
+) {
+    for (entity, asm_handle, mut sprite, mut maybe_instance) in query.iter_mut() {
+        // The ASM may still be loading
+        let Some(asm) = asms.get(asm_handle) else {
+            continue;
+        };
+        // Seeded by the entity so every entity makes its own, reproducible, random choices
+        let instance = match &mut maybe_instance.0 {
+            Some(instance) => instance,
+            None => match asm.try_default_instance_seeded(entity.to_bits()) {
+                Ok(instance) => maybe_instance.0.insert(instance),
+                Err(error) => {
+                    error!("Failed to start animation: {error}");
+                    continue;
+                }
+            },
+        };
+        let report = match asm.0.try_update(
+            &mut instance.0,
+            UpdateArgs {
+                delta_ms: time.delta_seconds_f64() * 1000. * time_scale.factor(),
+            },
+            &mut sprite,
+        ) {
+            Ok(report) => report,
+            Err(error) => {
+                error!("Failed to update animation: {error}");
+                continue;
+            }
+        };
+        send_report(entity, asm, report, &mut markers, &mut state_changes);
+    }
+}
+
+/// Run the animations across bundles of `BevyASM` and `BevyStateInstance` in parallel, see
+/// `AnimationUpdateMode::Parallel`
+///
+/// The entities are updated in place on the compute task pool, each looking up its own `BevyASM`.
+/// Sends the same events as the `animation_system`, in the order of the entities.
+pub fn parallel_animation_system(
+    time: Res<Time>,
+    time_scale: Res<AnimationTimeScale>,
+    asms: Res<Assets<BevyASM>>,
+    mut query: Query<
+        (
+            Entity,
+            &Handle<BevyASM>,
+            &mut TextureAtlasSprite,
+            &mut MaybeBevyStateInstance,
+        ),
+        Standalone,
+    >,
+    mut reports: Local<AnimationReports>,
+    mut markers: EventWriter<AnimationMarker>,
+    mut state_changes: EventWriter<StateChanged>,
+) {
+    let args = UpdateArgs {
+        delta_ms: time.delta_seconds_f64() * 1000. * time_scale.factor(),
+    };
+    let asms = &*asms;
+    let collected = &reports.0;
+    query
+        .par_iter_mut()
+        .batching_strategy(BatchingStrategy::new().min_batch_size(MIN_BATCH_SIZE))
+        .for_each_mut(|(entity, asm_handle, mut sprite, mut maybe_instance)| {
+            // The ASM may still be loading
+            let Some(asm) = asms.get(asm_handle) else {
+                return;
+            };
+            let instance = match &mut maybe_instance.0 {
+                Some(instance) => instance,
+                None => match asm.try_default_instance_seeded(entity.to_bits()) {
+                    Ok(instance) => maybe_instance.0.insert(instance),
+                    Err(error) => {
+                        error!("Failed to start animation: {error}");
+                        return;
+                    }
+                },
+            };
+            match asm.0.try_update(&mut instance.0, args, &mut sprite) {
+                Ok(report) if report.is_empty() => {}
+                Ok(report) => collected
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push((entity, asm_handle.id(), report)),
+                Err(error) => error!("Failed to update animation: {error}"),
+            }
+        });
+
+    let reports = reports.0.get_mut().unwrap_or_else(PoisonError::into_inner);
+    // The entities are updated in no particular order
+    reports.sort_unstable_by_key(|(entity, _, _)| *entity);
+    for (entity, asm_id, report) in reports.drain(..) {
+        if let Some(asm) = asms.get(&Handle::weak(asm_id)) {
+            send_report(entity, asm, report, &mut markers, &mut state_changes);
+        }
+    }
+}
+
+/// The fewest entities the `parallel_animation_system` updates in a task, below which spreading them
+/// out costs more than it saves
+const MIN_BATCH_SIZE: usize = 64;
+
+#[derive(Default)]
+/// The reports of the entities updated by the `parallel_animation_system` with their `BevyASM`, kept
+/// between runs so the buffer is reused
+pub struct AnimationReports(Mutex<Vec<(Entity, HandleId, UpdateReport)>>);
+
+/// Run the animations of every `AnimationLayer`, after their base layers were updated
+///
+/// Sends the same events as the `animation_system`.
+pub fn layer_system(
+    time: Res<Time>,
+    time_scale: Res<AnimationTimeScale>,
     asms: Res<Assets<BevyASM>>,
-    mut query: Query<(
+    mut layers: Query<(
+        Entity,
+        &AnimationLayer,
         &Handle<BevyASM>,
         &mut TextureAtlasSprite,
         &mut MaybeBevyStateInstance,
     )>,
+    bases: Query<(&Handle<BevyASM>, &MaybeBevyStateInstance), Without<AnimationLayer>>,
+    mut markers: EventWriter<AnimationMarker>,
+    mut state_changes: EventWriter<StateChanged>,
 ) {
-    for (asm_handle, mut sprite, mut maybe_instance) in query.iter_mut() {
-        let asm = asms.get(&asm_handle).unwrap();
-        let instance = maybe_instance.0.get_or_insert(asm.default_instance());
-        asm.0.update(
+    for (entity, layer, asm_handle, mut sprite, mut maybe_instance) in layers.iter_mut() {
+        // The base or either ASM may still be loading
+        let Ok((base_handle, base_instance)) = bases.get(layer.base) else {
+            continue;
+        };
+        let (Some(asm), Some(base_asm), Some(base_instance)) = (
+            asms.get(asm_handle),
+            asms.get(base_handle),
+            base_instance.0.as_ref(),
+        ) else {
+            continue;
+        };
+        let instance = match &mut maybe_instance.0 {
+            Some(instance) => instance,
+            None => match asm.try_default_instance_seeded(entity.to_bits()) {
+                Ok(instance) => maybe_instance.0.insert(instance),
+                Err(error) => {
+                    error!("Failed to start animation layer: {error}");
+                    continue;
+                }
+            },
+        };
+        let report = match asm.0.try_update_layer(
             &mut instance.0,
             UpdateArgs {
-                delta_ms: time.delta_seconds_f64() * 1000.,
+                delta_ms: time.delta_seconds_f64() * 1000. * time_scale.factor(),
             },
             &mut sprite,
-        )
+            &base_instance.0.current_id,
+            base_asm.0.normalized_time(&base_instance.0),
+            layer.options,
+        ) {
+            Ok(report) => report,
+            Err(error) => {
+                error!("Failed to update animation layer: {error}");
+                continue;
+            }
+        };
+        send_report(entity, asm, report, &mut markers, &mut state_changes);
+    }
+}
+
+/// Advances the clocks of the `SyncGroups` and shows the matching frame on every `SyncGroupMember`.
+///
+/// Members are left alone by the other animation systems, so this is the only clock they follow.
+pub fn sync_group_system(
+    time: Res<Time>,
+    time_scale: Res<AnimationTimeScale>,
+    asms: Res<Assets<BevyASM>>,
+    mut groups: ResMut<SyncGroups>,
+    mut members: Query<(
+        Entity,
+        &mut SyncGroupMember,
+        &Handle<BevyASM>,
+        &mut TextureAtlasSprite,
+        &mut MaybeBevyStateInstance,
+    )>,
+) {
+    groups.advance(time.delta_seconds_f64() * 1000. * time_scale.factor());
+    for (entity, mut member, asm_handle, mut sprite, mut maybe_instance) in members.iter_mut() {
+        // The ASM may still be loading
+        let Some(asm) = asms.get(asm_handle) else {
+            continue;
+        };
+        let instance = match &mut maybe_instance.0 {
+            Some(instance) => instance,
+            None => match asm.try_default_instance_seeded(entity.to_bits()) {
+                Ok(instance) => maybe_instance.0.insert(instance),
+                Err(error) => {
+                    error!("Failed to start animation: {error}");
+                    continue;
+                }
+            },
+        };
+        let clock_ms = groups.member_time_ms(&mut member);
+        if let Err(error) = asm.0.sync_to_clock(&mut instance.0, clock_ms, &mut sprite) {
+            error!("Failed to sync animation: {error}");
+        }
+    }
+}
+
+/// Sends the events for everything of note in the report of `asm`, naming the states by their IDs
+fn send_report(
+    entity: Entity,
+    asm: &BevyASM,
+    report: UpdateReport,
+    markers: &mut EventWriter<AnimationMarker>,
+    state_changes: &mut EventWriter<StateChanged>,
+) {
+    // The report's indices were resolved by `asm`, so every state exists
+    let id = |index| asm.0.id_at(index).cloned().unwrap_or_default();
+    markers.send_batch(report.markers.into_iter().map(|marker| AnimationMarker {
+        entity,
+        state: id(marker.state),
+        name: marker.name,
+    }));
+    state_changes.send_batch(
+        report
+            .transitions
+            .into_iter()
+            .map(|transition| StateChanged {
+                entity,
+                from: id(transition.from),
+                to: id(transition.to),
+                reason: transition.reason,
+            }),
+    );
+}
+
+/// Applies `AnimationCommand`s to the entities they target
+///
+/// Sends a `StateChanged` event for every state change caused by a command.
+pub fn animation_command_system(
+    asms: Res<Assets<BevyASM>>,
+    mut commands: EventReader<AnimationCommand>,
+    mut query: Query<(&Handle<BevyASM>, &mut MaybeBevyStateInstance)>,
+    mut state_changes: EventWriter<StateChanged>,
+) {
+    for command in commands.iter() {
+        let (entity, state, already_playing, queued) = match command {
+            AnimationCommand::Play {
+                entity,
+                state,
+                already_playing,
+            } => (*entity, state, *already_playing, false),
+            AnimationCommand::PlayQueued {
+                entity,
+                state,
+                already_playing,
+            } => (*entity, state, *already_playing, true),
+        };
+        let Ok((asm_handle, mut maybe_instance)) = query.get_mut(entity) else {
+            error!("Failed to play \"{state}\": {entity:?} has no animation");
+            continue;
+        };
+        let Some(asm) = asms.get(asm_handle) else {
+            error!("Failed to play \"{state}\": the animation of {entity:?} is still loading");
+            continue;
+        };
+        let instance = match &mut maybe_instance.0 {
+            Some(instance) => instance,
+            None => match asm.try_default_instance_seeded(entity.to_bits()) {
+                Ok(instance) => maybe_instance.0.insert(instance),
+                Err(error) => {
+                    error!("Failed to start animation: {error}");
+                    continue;
+                }
+            },
+        };
+
+        if queued {
+            if let Err(error) = asm
+                .0
+                .play_queued(&mut instance.0, state.clone(), already_playing)
+            {
+                error!("Failed to queue animation: {error}");
+            }
+            continue;
+        }
+        match asm.0.play(&mut instance.0, state.clone(), already_playing) {
+            Ok(Some(transition)) => state_changes.send(StateChanged {
+                entity,
+                from: asm.0.id_at(transition.from).cloned().unwrap_or_default(),
+                to: asm.0.id_at(transition.to).cloned().unwrap_or_default(),
+                reason: transition.reason,
+            }),
+            Ok(None) => {}
+            Err(error) => error!("Failed to play animation: {error}"),
+        }
+    }
+}
+
+/// Shows the outgoing state of crossfades on every `CrossfadeOverlay`
+pub fn crossfade_system(
+    mut overlays: Query<(
+        &CrossfadeOverlay,
+        &mut TextureAtlasSprite,
+        &mut Handle<TextureAtlas>,
+        &mut Visibility,
+    )>,
+    targets: Query<(&Handle<TextureAtlas>, &MaybeBevyStateInstance), Without<CrossfadeOverlay>>,
+) {
+    for (overlay, mut sprite, mut atlas, mut visibility) in overlays.iter_mut() {
+        let crossfade = targets
+            .get(overlay.target)
+            .ok()
+            .and_then(|(target_atlas, instance)| {
+                let crossfade = instance.0.as_ref()?.0.crossfade.as_ref()?;
+                Some((target_atlas, crossfade))
+            });
+        let Some((target_atlas, crossfade)) = crossfade else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+
+        if *atlas != *target_atlas {
+            *atlas = target_atlas.clone();
+        }
+        sprite.index = crossfade.from_index;
+        sprite.color.set_a(1. - crossfade.weight() as f32);
+        *visibility = Visibility::Inherited;
     }
 }
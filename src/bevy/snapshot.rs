@@ -0,0 +1,116 @@
+use bevy::{
+    prelude::{Entity, World},
+    sprite::TextureAtlasSprite,
+};
+
+use crate::{
+    bevy::{MaybeBevyStateInstance, SyncGroupMember, SyncGroups},
+    state_machine::{Checksum, StableHash},
+};
+
+#[derive(Debug, Clone, Default)]
+/// A copy of everything that determines the animations of a `World`, for rollback netcode.
+///
+/// Restoring a snapshot and resimulating the same frames with the same time deltas produces
+/// bit-identical animations. Taking one clones every instance, see `StateInstance`.
+pub struct AnimationSnapshot {
+    /// Every animated entity, sorted by entity
+    pub entities: Vec<EntitySnapshot>,
+    /// The clocks of every sync group
+    pub sync_groups: SyncGroups,
+}
+
+#[derive(Debug, Clone)]
+/// The animation state of a single entity in an `AnimationSnapshot`
+pub struct EntitySnapshot {
+    /// The animated entity
+    pub entity: Entity,
+    /// The entity's animation instance
+    pub instance: MaybeBevyStateInstance,
+    /// The sprite index the entity was showing
+    pub sprite_index: Option<usize>,
+    /// The entity's sync group membership, which remembers its random offset
+    pub sync_member: Option<SyncGroupMember>,
+}
+
+impl AnimationSnapshot {
+    /// Copies the animation state of every entity with a `MaybeBevyStateInstance`
+    pub fn take(world: &mut World) -> Self {
+        let mut query = world.query::<(
+            Entity,
+            &MaybeBevyStateInstance,
+            Option<&TextureAtlasSprite>,
+            Option<&SyncGroupMember>,
+        )>();
+        let mut entities: Vec<_> = query
+            .iter(world)
+            .map(|(entity, instance, sprite, sync_member)| EntitySnapshot {
+                entity,
+                instance: instance.clone(),
+                sprite_index: sprite.map(|sprite| sprite.index),
+                sync_member: sync_member.cloned(),
+            })
+            .collect();
+        // Query order depends on the archetypes, which may differ between peers
+        entities.sort_unstable_by_key(|snapshot| snapshot.entity);
+        AnimationSnapshot {
+            entities,
+            sync_groups: world
+                .get_resource::<SyncGroups>()
+                .cloned()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Puts the animation state of every entity in the snapshot back to how it was when the snapshot
+    /// was taken.
+    ///
+    /// Only the entities in the snapshot are touched: entities that were animated since the snapshot
+    /// was taken keep their animation, and entities that no longer exist are skipped. Removing
+    /// entities spawned since the snapshot is up to the rollback itself.
+    pub fn restore(&self, world: &mut World) {
+        for snapshot in &self.entities {
+            let Some(mut entity) = world.get_entity_mut(snapshot.entity) else {
+                continue;
+            };
+            if let Some(mut instance) = entity.get_mut::<MaybeBevyStateInstance>() {
+                *instance = snapshot.instance.clone();
+            }
+            if let (Some(mut sprite), Some(index)) = (
+                entity.get_mut::<TextureAtlasSprite>(),
+                snapshot.sprite_index,
+            ) {
+                sprite.index = index;
+            }
+            match &snapshot.sync_member {
+                Some(member) => {
+                    entity.insert(member.clone());
+                }
+                None => {
+                    entity.remove::<SyncGroupMember>();
+                }
+            }
+        }
+        world.insert_resource(self.sync_groups.clone());
+    }
+}
+
+impl StableHash for AnimationSnapshot {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        self.entities.stable_hash(checksum);
+        self.sync_groups.stable_hash(checksum);
+    }
+}
+
+impl StableHash for EntitySnapshot {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        self.entity.to_bits().stable_hash(checksum);
+        self.instance
+            .0
+            .as_ref()
+            .map(|instance| &instance.0)
+            .stable_hash(checksum);
+        self.sprite_index.stable_hash(checksum);
+        self.sync_member.stable_hash(checksum);
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use bevy::prelude::{Component, Resource};
+
+use crate::state_machine::{Checksum, SeededRng, StableHash};
+
+#[derive(Debug, Clone, PartialEq, Component)]
+/// Makes an animated entity a member of a sync group, so it animates in lockstep with the other members.
+///
+/// Members are not advanced by the `animation_system` or `parallel_animation_system`, they ignore
+/// their own timing and show the frame at the group's clock plus their offset, so
+/// entities spawned later join the group mid-cycle.
+pub struct SyncGroupMember {
+    /// The name of the group, groups are created the first time a member joins them
+    pub group: String,
+    /// How far ahead of the group's clock the member plays, in ms
+    pub offset_ms: f64,
+    /// If above 0, a random offset in `[0, random_offset_ms)` is added to `offset_ms` when the member joins
+    pub random_offset_ms: f64,
+    joined_offset_ms: Option<f64>,
+}
+
+impl SyncGroupMember {
+    /// Creates a member of `group` without any offset
+    pub fn new(group: impl Into<String>) -> Self {
+        Self {
+            group: group.into(),
+            offset_ms: 0.,
+            random_offset_ms: 0.,
+            joined_offset_ms: None,
+        }
+    }
+
+    /// Sets how far ahead of the group's clock the member plays, in ms
+    pub fn with_offset(mut self, offset_ms: f64) -> Self {
+        self.offset_ms = offset_ms;
+        self
+    }
+
+    /// Adds a random offset in `[0, random_offset_ms)` when the member joins, e.g. the length of a
+    /// loop to spread the members over the whole cycle
+    pub fn with_random_offset(mut self, random_offset_ms: f64) -> Self {
+        self.random_offset_ms = random_offset_ms;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, Resource)]
+/// The clocks of every sync group, see `SyncGroupMember`
+pub struct SyncGroups {
+    clocks: HashMap<String, f64>,
+    rng: SeededRng,
+}
+
+impl SyncGroups {
+    /// Creates the groups with a seed for the members' random offsets
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            clocks: HashMap::new(),
+            rng: SeededRng::new(seed),
+        }
+    }
+
+    /// The number of ms the group's clock has run for, if it exists
+    pub fn clock_ms(&self, group: &str) -> Option<f64> {
+        self.clocks.get(group).copied()
+    }
+
+    /// Sets the group's clock, e.g. to restart every member at once
+    pub fn set_clock_ms(&mut self, group: impl Into<String>, clock_ms: f64) {
+        self.clocks.insert(group.into(), clock_ms);
+    }
+
+    /// Advances every group's clock by `delta_ms`
+    pub fn advance(&mut self, delta_ms: f64) {
+        for clock_ms in self.clocks.values_mut() {
+            *clock_ms += delta_ms;
+        }
+    }
+
+    /// The time `member` should show, joining its group if it hasn't yet
+    pub fn member_time_ms(&mut self, member: &mut SyncGroupMember) -> f64 {
+        let joined_offset_ms = *member
+            .joined_offset_ms
+            .get_or_insert_with(|| self.rng.range_f64(0., member.random_offset_ms));
+        let clock_ms = match self.clocks.get(&member.group) {
+            Some(clock_ms) => *clock_ms,
+            None => *self.clocks.entry(member.group.clone()).or_default(),
+        };
+        clock_ms + member.offset_ms + joined_offset_ms
+    }
+}
+
+impl StableHash for SyncGroupMember {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        self.group.stable_hash(checksum);
+        self.offset_ms.stable_hash(checksum);
+        self.random_offset_ms.stable_hash(checksum);
+        self.joined_offset_ms.stable_hash(checksum);
+    }
+}
+
+impl StableHash for SyncGroups {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        self.clocks.stable_hash(checksum);
+        self.rng.stable_hash(checksum);
+    }
+}
@@ -0,0 +1,246 @@
+use std::fmt::{self, Display};
+
+use crate::state_machine::StateID;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The problems that can occur while running an `AnimationStateMachine`
+pub enum Error {
+    /// The state machine has no state with the given ID
+    MissingState(StateID),
+    /// The state machine's default ID does not refer to any of its states or groups
+    MissingDefaultState(StateID),
+    /// A state or transition tried to move to a state that does not exist
+    MissingTarget {
+        /// The state being left
+        from: StateID,
+        /// The state that could not be found
+        to: StateID,
+    },
+    /// The state machine failed validation, see `AnimationStateMachine::validate`
+    Invalid(Vec<ValidationError>),
+    /// A document could not be imported as a state machine
+    Import(String),
+    /// A document was written in a newer version of its format than this version of the crate knows
+    UnsupportedVersion {
+        /// The version of the document
+        found: u32,
+        /// The newest version this version of the crate can read
+        latest: u32,
+    },
+    /// A `LayeredStateMachine` was updated with the wrong number of sprites or layer instances
+    LayerMismatch {
+        /// The number of layers in the machine
+        layers: usize,
+        /// The number of sprites or layer instances given
+        found: usize,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingState(id) => write!(f, "no state with ID \"{id}\""),
+            Error::MissingDefaultState(id) => {
+                write!(f, "the default state \"{id}\" does not exist")
+            }
+            Error::MissingTarget { from, to } => {
+                write!(
+                    f,
+                    "state \"{from}\" moves to \"{to}\", which does not exist"
+                )
+            }
+            Error::Invalid(problems) => {
+                write!(f, "invalid state machine:")?;
+                for problem in problems {
+                    write!(f, "\n  - {problem}")?;
+                }
+                Ok(())
+            }
+            Error::Import(reason) => write!(f, "failed to import: {reason}"),
+            Error::UnsupportedVersion { found, latest } => write!(
+                f,
+                "the document is version {found}, but only versions up to {latest} are supported"
+            ),
+            Error::LayerMismatch { layers, found } => write!(
+                f,
+                "the state machine has {layers} layers, but {found} were given"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A single problem found while validating an `AnimationStateMachine`
+pub enum ValidationError {
+    /// The state machine's default ID does not refer to any of its states or groups
+    MissingDefaultState(StateID),
+    /// A state or transition moves to a state that does not exist
+    MissingTarget {
+        /// The state being left
+        from: StateID,
+        /// The state that could not be found
+        to: StateID,
+    },
+    /// A transition leaves a state that does not exist
+    MissingSource(StateID),
+    /// An any-state transition moves to a state that does not exist
+    MissingAnyStateTarget(StateID),
+    /// A state's first frame comes after its last frame
+    InvalidRange {
+        /// The state with the invalid range
+        state: StateID,
+        /// The state's first frame
+        min: usize,
+        /// The state's last frame
+        max: usize,
+    },
+    /// A state has no frames to show
+    NoFrames(StateID),
+    /// A state's frames are shown for a negative, zero or infinite amount of time
+    InvalidFrameRate(StateID),
+    /// A state's fluidity factor is outside of (0, 1]
+    InvalidFluidity(StateID),
+    /// A frame of a state is shown for a negative or infinite amount of time, or NaN
+    InvalidFrameDuration {
+        /// The state
+        state: StateID,
+        /// The position of the frame in the state's frames
+        position: usize,
+    },
+    /// More than one state was given the same ID
+    DuplicateState(StateID),
+    /// The state machine has no states at all
+    NoStates,
+    /// A random choice state has no choice with a positive weight
+    NoChoices(StateID),
+    /// A choice of a random choice state has a negative or infinite weight, or NaN
+    InvalidWeight {
+        /// The random choice state
+        state: StateID,
+        /// The position of the choice in the state's choices
+        position: usize,
+    },
+    /// A state's phase shift is negative or infinite, or its random phase range is reversed
+    InvalidPhase(StateID),
+    /// A state uses a frame outside of the frame source
+    FrameOutOfBounds {
+        /// The state using the frame
+        state: StateID,
+        /// The frame index that is out of bounds
+        index: usize,
+        /// The number of frames in the frame source
+        frame_count: usize,
+    },
+    /// A state can never be reached from the default state
+    Unreachable(StateID),
+    /// A group's default ID is not one of its members
+    InvalidGroupDefault {
+        /// The group with the invalid default
+        group: StateID,
+        /// The default ID that is not a member
+        default: StateID,
+    },
+    /// A group contains a state or group that does not exist
+    MissingMember {
+        /// The group containing the member
+        group: StateID,
+        /// The member that could not be found
+        member: StateID,
+    },
+    /// A state or group is a member of more than one group
+    DuplicateMember(StateID),
+    /// A state and a group share the same ID
+    DuplicateId(StateID),
+    /// A group contains itself, directly or through other groups
+    GroupCycle(StateID),
+}
+
+impl ValidationError {
+    /// Whether the problem would cause the state machine to fail at runtime.
+    ///
+    /// Unreachable states are harmless and may still be entered by gameplay code.
+    pub fn is_fatal(&self) -> bool {
+        !matches!(self, ValidationError::Unreachable(_))
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::MissingDefaultState(id) => {
+                write!(f, "the default state \"{id}\" does not exist")
+            }
+            ValidationError::MissingTarget { from, to } => {
+                write!(
+                    f,
+                    "state \"{from}\" moves to \"{to}\", which does not exist"
+                )
+            }
+            ValidationError::MissingAnyStateTarget(id) => write!(
+                f,
+                "an any-state transition moves to \"{id}\", which does not exist"
+            ),
+            ValidationError::MissingSource(id) => {
+                write!(f, "a transition leaves \"{id}\", which does not exist")
+            }
+            ValidationError::InvalidRange { state, min, max } => write!(
+                f,
+                "state \"{state}\" starts at frame {min}, which is after its last frame {max}"
+            ),
+            ValidationError::NoFrames(id) => write!(f, "state \"{id}\" has no frames"),
+            ValidationError::InvalidFrameRate(id) => {
+                write!(f, "state \"{id}\" has an invalid frame rate")
+            }
+            ValidationError::InvalidFluidity(id) => {
+                write!(f, "state \"{id}\" has a fluidity outside of (0, 1]")
+            }
+            ValidationError::InvalidFrameDuration { state, position } => {
+                write!(f, "frame {position} of state \"{state}\" has an invalid duration")
+            }
+            ValidationError::DuplicateState(id) => {
+                write!(f, "more than one state has the ID \"{id}\"")
+            }
+            ValidationError::NoStates => write!(f, "the state machine has no states"),
+            ValidationError::NoChoices(id) => {
+                write!(f, "state \"{id}\" has no choices with a positive weight")
+            }
+            ValidationError::InvalidWeight { state, position } => {
+                write!(f, "choice {position} of state \"{state}\" has an invalid weight")
+            }
+            ValidationError::InvalidPhase(id) => {
+                write!(f, "state \"{id}\" has an invalid phase")
+            }
+            ValidationError::FrameOutOfBounds {
+                state,
+                index,
+                frame_count,
+            } => write!(
+                f,
+                "state \"{state}\" uses frame {index}, but the frame source only has {frame_count} frames"
+            ),
+            ValidationError::Unreachable(id) => {
+                write!(f, "state \"{id}\" can not be reached from the default state")
+            }
+            ValidationError::InvalidGroupDefault { group, default } => write!(
+                f,
+                "group \"{group}\" defaults to \"{default}\", which is not one of its members"
+            ),
+            ValidationError::MissingMember { group, member } => write!(
+                f,
+                "group \"{group}\" contains \"{member}\", which does not exist"
+            ),
+            ValidationError::DuplicateMember(id) => {
+                write!(f, "\"{id}\" is a member of more than one group")
+            }
+            ValidationError::DuplicateId(id) => {
+                write!(f, "\"{id}\" is the ID of both a state and a group")
+            }
+            ValidationError::GroupCycle(id) => write!(f, "group \"{id}\" contains itself"),
+        }
+    }
+}
+
+/// A `Result` whose error defaults to the crate's `Error`
+pub type Result<T, E = Error> = std::result::Result<T, E>;
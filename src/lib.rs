@@ -1,11 +1,16 @@
 //! A state machine thing for animating sprites with bevy.
 // #![deny(warnings)]
 #![warn(missing_docs)]
+/// The errors reported by the state machine
+pub mod error;
 /// The base logic for switching between animation states
 pub mod state_machine;
 /// The types of states that can be switched between
 pub mod states;
 
+#[cfg(feature = "aseprite")]
+/// Importing animations exported from Aseprite
+pub mod aseprite;
 #[cfg(feature = "bevy")]
 /// Plugin support for the Bevy engine
 pub mod bevy;
@@ -0,0 +1,182 @@
+use core::marker::PhantomData;
+use std::collections::HashSet;
+
+use super::{
+    AnimationState, AnimationStateMachine, AnyStateTransition, Sprite, StateContainer, StateGroup,
+    StateID, Transition,
+};
+use crate::{
+    error::{Error, Result, ValidationError},
+    states::{
+        frame_list::{FrameListState, FrameListStateBuilder},
+        index::{IndexState, IndexStateBuilder},
+    },
+};
+
+#[derive(Debug)]
+/// Builds an `AnimationStateMachine` in code, see `AnimationStateMachine::builder`.
+///
+/// Problems are collected as the machine is built and reported together by `build`, which also
+/// validates the finished machine.
+pub struct AnimationStateMachineBuilder<Sprite, State, FrameSource> {
+    frame_source: FrameSource,
+    default_id: Option<StateID>,
+    states: StateContainer<State>,
+    groups: Vec<(StateID, StateGroup)>,
+    transitions: Vec<Transition>,
+    any_state_transitions: Vec<AnyStateTransition>,
+    problems: Vec<ValidationError>,
+    /// Every state ID added, including those that failed to build
+    added: HashSet<StateID>,
+    phantom: PhantomData<Sprite>,
+}
+
+impl<S, T, F> AnimationStateMachine<S, T, F>
+where
+    S: Sprite,
+    T: AnimationState<Sprite = S>,
+    F: Default,
+{
+    /// Starts building a machine over the default frame source, e.g.
+    /// `AnimationStateMachine::builder().state("walk", |s| s.frames(0..=9).fps(15).then("idle"))`
+    pub fn builder() -> AnimationStateMachineBuilder<S, T, F> {
+        AnimationStateMachineBuilder::new(F::default())
+    }
+}
+
+impl<S, T, F> AnimationStateMachineBuilder<S, T, F>
+where
+    S: Sprite,
+    T: AnimationState<Sprite = S>,
+{
+    /// Starts building a machine over `frame_source`
+    pub fn new(frame_source: F) -> Self {
+        Self {
+            frame_source,
+            default_id: None,
+            states: StateContainer::default(),
+            groups: Vec::new(),
+            transitions: Vec::new(),
+            any_state_transitions: Vec::new(),
+            problems: Vec::new(),
+            added: HashSet::new(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets the frame source the machine's frames come from
+    pub fn frame_source(mut self, frame_source: F) -> Self {
+        self.frame_source = frame_source;
+        self
+    }
+
+    /// Sets the state or group instances start in, by default the first state added
+    pub fn default_state(mut self, id: impl Into<StateID>) -> Self {
+        self.default_id = Some(id.into());
+        self
+    }
+
+    /// Adds an `IndexState` set up by `build`, e.g. `|s| s.frames(0..=9).fps(15).then("idle")`
+    pub fn state(
+        self,
+        id: impl Into<StateID>,
+        build: impl FnOnce(IndexStateBuilder<S>) -> IndexStateBuilder<S>,
+    ) -> Self
+    where
+        T: From<IndexState<S>>,
+    {
+        let id = id.into();
+        let state = build(IndexState::builder()).build(&id).map(T::from);
+        self.add_built(id, state)
+    }
+
+    /// Adds a `FrameListState` set up by `build`, e.g. `|s| s.frame(3, 100).frame(5, 60)`
+    pub fn frame_list(
+        self,
+        id: impl Into<StateID>,
+        build: impl FnOnce(FrameListStateBuilder<S>) -> FrameListStateBuilder<S>,
+    ) -> Self
+    where
+        T: From<FrameListState<S>>,
+    {
+        let id = id.into();
+        let state = build(FrameListState::builder()).build(&id).map(T::from);
+        self.add_built(id, state)
+    }
+
+    /// Adds a state that was made elsewhere
+    pub fn add_state(self, id: impl Into<StateID>, state: impl Into<T>) -> Self {
+        self.add_built(id.into(), Ok(state.into()))
+    }
+
+    /// Adds a group of states, see `AnimationStateMachine::add_groups`
+    pub fn group(
+        mut self,
+        id: impl Into<StateID>,
+        default_id: impl Into<StateID>,
+        members: impl IntoIterator<Item = impl Into<StateID>>,
+    ) -> Self {
+        let group = StateGroup::new(
+            default_id.into(),
+            members.into_iter().map(Into::into).collect(),
+        );
+        self.groups.push((id.into(), group));
+        self
+    }
+
+    /// Adds a conditional transition, see `AnimationStateMachine::add_transitions`
+    pub fn transition(mut self, transition: Transition) -> Self {
+        self.transitions.push(transition);
+        self
+    }
+
+    /// Adds a transition that can be taken from any state, see `AnimationStateMachine::add_any_state_transitions`
+    pub fn any_state_transition(mut self, transition: AnyStateTransition) -> Self {
+        self.any_state_transitions.push(transition);
+        self
+    }
+
+    /// Builds the machine
+    ///
+    /// # Returns
+    /// * `Ok(machine)` if every state could be built and the machine passes `validate`
+    /// * `Err(Error::Invalid(problems))` with the problems of the states that could not be built, or
+    ///   the problems `validate` found otherwise
+    pub fn build(self) -> Result<AnimationStateMachine<S, T, F>> {
+        let mut problems = self.problems;
+        // A state that failed to build has already been reported
+        if self.added.is_empty() {
+            problems.push(ValidationError::NoStates);
+        }
+        if !problems.is_empty() {
+            // Validating now would also report every reference to the states that failed
+            return Err(Error::Invalid(problems));
+        }
+
+        let default_id = self
+            .default_id
+            .or_else(|| self.states.ids().next().cloned())
+            .unwrap_or_default();
+        let mut asm =
+            AnimationStateMachine::with_states(self.frame_source, default_id, self.states);
+        asm.add_groups(self.groups);
+        asm.add_transitions(self.transitions);
+        asm.add_any_state_transitions(self.any_state_transitions);
+        asm.validate(None)?;
+        Ok(asm)
+    }
+
+    fn add_built(mut self, id: StateID, state: Result<T, ValidationError>) -> Self {
+        if !self.added.insert(id.clone()) {
+            self.problems.push(ValidationError::DuplicateState(id));
+            return self;
+        }
+        match state {
+            Ok(state) => {
+                self.states.insert(id, state);
+            }
+            Err(problem) => self.problems.push(problem),
+        }
+        self
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use super::{Parameter, Parameters, StateID};
+
+const FNV_OFFSET: u64 = 0xCBF2_9CE4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01B3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A hasher (FNV-1a) whose result only depends on the values fed to it.
+///
+/// Unlike `std::hash::DefaultHasher` it is the same across runs, builds and machines, so the checksums
+/// of two peers can be compared, e.g. to detect desyncs in rollback netcode.
+pub struct Checksum(u64);
+
+impl Checksum {
+    /// Creates an empty checksum
+    pub fn new() -> Self {
+        Checksum(FNV_OFFSET)
+    }
+
+    /// Feeds raw bytes into the checksum
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    /// Feeds a `u64` into the checksum
+    pub fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    /// The checksum of everything fed in so far
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for Checksum {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Values that can be fed into a `Checksum`.
+///
+/// Floats are hashed by their bits, so values are only equal if they are bit-identical.
+pub trait StableHash {
+    /// Feeds the value into `checksum`
+    fn stable_hash(&self, checksum: &mut Checksum);
+
+    /// The checksum of the value on its own
+    fn checksum(&self) -> u64 {
+        let mut checksum = Checksum::new();
+        self.stable_hash(&mut checksum);
+        checksum.finish()
+    }
+}
+
+impl StableHash for u64 {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        checksum.write_u64(*self);
+    }
+}
+
+impl StableHash for i64 {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        checksum.write(&self.to_le_bytes());
+    }
+}
+
+impl StableHash for usize {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        // Hashed as a u64 so 32 and 64 bit peers agree
+        checksum.write_u64(*self as u64);
+    }
+}
+
+impl StableHash for f64 {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        checksum.write_u64(self.to_bits());
+    }
+}
+
+impl StableHash for bool {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        checksum.write(&[*self as u8]);
+    }
+}
+
+impl StableHash for str {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        self.len().stable_hash(checksum);
+        checksum.write(self.as_bytes());
+    }
+}
+
+impl StableHash for String {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        self.as_str().stable_hash(checksum);
+    }
+}
+
+impl<T: StableHash + ?Sized> StableHash for &T {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        (**self).stable_hash(checksum);
+    }
+}
+
+impl<T: StableHash> StableHash for Option<T> {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        match self {
+            Some(value) => {
+                true.stable_hash(checksum);
+                value.stable_hash(checksum);
+            }
+            None => false.stable_hash(checksum),
+        }
+    }
+}
+
+impl<T: StableHash> StableHash for [T] {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        self.len().stable_hash(checksum);
+        for value in self {
+            value.stable_hash(checksum);
+        }
+    }
+}
+
+impl<T: StableHash> StableHash for Vec<T> {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        self.as_slice().stable_hash(checksum);
+    }
+}
+
+impl<A: StableHash, B: StableHash> StableHash for (A, B) {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        self.0.stable_hash(checksum);
+        self.1.stable_hash(checksum);
+    }
+}
+
+impl<V: StableHash> StableHash for HashMap<String, V> {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        // The iteration order of a HashMap differs between runs
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_unstable_by_key(|(name, _)| *name);
+        entries.stable_hash(checksum);
+    }
+}
+
+impl StableHash for StateID {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        self.0.stable_hash(checksum);
+    }
+}
+
+impl StableHash for Parameter {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        match self {
+            Parameter::Bool(value) => (0_u64, *value as u64).stable_hash(checksum),
+            Parameter::Float(value) => (1_u64, value.to_bits()).stable_hash(checksum),
+            Parameter::Int(value) => (2_u64, *value as u64).stable_hash(checksum),
+            Parameter::Trigger(value) => (3_u64, *value as u64).stable_hash(checksum),
+        }
+    }
+}
+
+impl StableHash for Parameters {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        // Hashed like a map, as the slots depend on the order the parameters were set in
+        let mut entries: Vec<_> = self.iter().collect();
+        entries.sort_unstable_by_key(|(name, _)| *name);
+        entries.stable_hash(checksum);
+    }
+}
@@ -0,0 +1,256 @@
+#[cfg(feature = "bevy")]
+use bevy::prelude::Component;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    AlreadyPlaying, AnimationState, AnimationStateMachine, Checksum, Parameters, Sprite,
+    StableHash, StateID, StateInstance, TransitionEvent, TransitionReason, UpdateArgs,
+    UpdateReport,
+};
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// How a layer chooses its state relative to its base layer
+pub enum LayerMode {
+    /// The layer only follows its own transitions, overriding whatever the base layer is doing
+    #[default]
+    Override,
+    /// The layer plays the same state as the base layer whenever it has a state or group with that ID.
+    /// Its own transitions are only taken while the base layer is in a state the layer doesn't have.
+    Follow,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// How a layer is kept in step with its base layer
+pub struct LayerOptions {
+    /// How the layer chooses its state
+    #[serde(default)]
+    pub mode: LayerMode,
+    /// Whether the layer's normalized time is set to the base layer's after every update
+    #[serde(default)]
+    pub sync_time: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+/// One of the state machines of a `LayeredStateMachine`, driving its own sprite
+pub struct Layer<Sprite, State, FrameSource> {
+    /// The name of the layer, e.g. "arms"
+    pub name: String,
+    /// The state machine driving the layer's sprite
+    pub machine: AnimationStateMachine<Sprite, State, FrameSource>,
+    /// How the layer is kept in step with the base layer
+    #[serde(default)]
+    pub options: LayerOptions,
+}
+
+impl<S, T, F> Layer<S, T, F> {
+    /// Creates a layer that only follows its own transitions
+    pub fn new(name: impl Into<String>, machine: AnimationStateMachine<S, T, F>) -> Self {
+        Self {
+            name: name.into(),
+            machine,
+            options: LayerOptions::default(),
+        }
+    }
+
+    /// Sets how the layer chooses its state
+    pub fn with_mode(mut self, mode: LayerMode) -> Self {
+        self.options.mode = mode;
+        self
+    }
+
+    /// Keeps the layer's normalized time in step with the base layer's
+    pub fn synced(mut self) -> Self {
+        self.options.sync_time = true;
+        self
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+/// Several state machines driving separate sprites of one logical instance, e.g. the body, arms and
+/// weapon of a character.
+///
+/// The first layer is the base layer, which the other layers can follow or sync their time to.
+pub struct LayeredStateMachine<Sprite, State, FrameSource> {
+    layers: Vec<Layer<Sprite, State, FrameSource>>,
+}
+
+impl<S, T, F> LayeredStateMachine<S, T, F>
+where
+    S: Sprite,
+    T: AnimationState<Sprite = S>,
+{
+    /// Creates a layered machine with `base` as its base layer
+    pub fn new(base: Layer<S, T, F>) -> Self {
+        Self { layers: vec![base] }
+    }
+
+    /// Adds a layer on top of the existing layers
+    pub fn add_layer(&mut self, layer: Layer<S, T, F>) {
+        self.layers.push(layer);
+    }
+
+    /// The layers, base layer first
+    pub fn layers(&self) -> &[Layer<S, T, F>] {
+        &self.layers
+    }
+
+    /// The layer with the given name
+    pub fn layer(&self, name: &str) -> Option<&Layer<S, T, F>> {
+        self.layers.iter().find(|layer| layer.name == name)
+    }
+
+    /// Creates a new instance with every layer in its default state
+    ///
+    /// # Panics
+    /// If the default state of a layer does not exist, see `try_default_instance`.
+    pub fn default_instance(&self) -> LayeredInstance<T, T::Data> {
+        self.try_default_instance()
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Creates a new instance with every layer in its default state
+    pub fn try_default_instance(&self) -> Result<LayeredInstance<T, T::Data>> {
+        Ok(LayeredInstance {
+            layers: self
+                .layers
+                .iter()
+                .map(|layer| layer.machine.try_default_instance())
+                .collect::<Result<_>>()?,
+            parameters: Parameters::default(),
+        })
+    }
+
+    /// Run an update cycle for every layer, base layer first, with one sprite per layer
+    ///
+    /// # Panics
+    /// If a layer fails to update, see `try_update`.
+    pub fn update(
+        &self,
+        instance: &mut LayeredInstance<T, T::Data>,
+        args: UpdateArgs,
+        sprites: &mut [S],
+    ) -> Vec<UpdateReport> {
+        self.try_update(instance, args, sprites)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Run an update cycle for every layer, base layer first, with one sprite per layer
+    ///
+    /// Every layer's transitions are evaluated with the instance's shared parameters.
+    ///
+    /// # Returns
+    /// The report of every layer, base layer first
+    pub fn try_update(
+        &self,
+        instance: &mut LayeredInstance<T, T::Data>,
+        args: UpdateArgs,
+        sprites: &mut [S],
+    ) -> Result<Vec<UpdateReport>> {
+        for found in [sprites.len(), instance.layers.len()] {
+            if found != self.layers.len() {
+                return Err(Error::LayerMismatch {
+                    layers: self.layers.len(),
+                    found,
+                });
+            }
+        }
+        let parameters = &mut instance.parameters;
+        let (
+            Some((base, layers)),
+            Some((base_instance, layer_instances)),
+            Some((base_sprite, layer_sprites)),
+        ) = (
+            self.layers.split_first(),
+            instance.layers.split_first_mut(),
+            sprites.split_first_mut(),
+        )
+        else {
+            return Ok(Vec::new());
+        };
+
+        let mut reports = Vec::with_capacity(self.layers.len());
+        core::mem::swap(&mut base_instance.parameters, parameters);
+        let report = base.machine.try_update(base_instance, args, base_sprite);
+        core::mem::swap(&mut base_instance.parameters, parameters);
+        reports.push(report?);
+
+        let base_time = base.machine.normalized_time(base_instance);
+        for ((layer, layer_instance), sprite) in layers
+            .iter()
+            .zip(layer_instances.iter_mut())
+            .zip(layer_sprites.iter_mut())
+        {
+            core::mem::swap(&mut layer_instance.parameters, parameters);
+            let report = layer.machine.try_update_layer(
+                layer_instance,
+                args,
+                sprite,
+                &base_instance.current_id,
+                base_time,
+                layer.options,
+            );
+            core::mem::swap(&mut layer_instance.parameters, parameters);
+            reports.push(report?);
+        }
+        Ok(reports)
+    }
+}
+
+impl<S, T, F> AnimationStateMachine<S, T, F>
+where
+    S: Sprite,
+    T: AnimationState<Sprite = S>,
+{
+    /// Run an update cycle for a layer whose base layer has already been updated this cycle
+    /// * `base_id` The current state of the base layer.
+    /// * `base_time` The normalized time of the base layer, see `AnimationState::normalized_time`.
+    pub fn try_update_layer(
+        &self,
+        instance: &mut StateInstance<T, T::Data>,
+        args: UpdateArgs,
+        sprite: &mut S,
+        base_id: &StateID,
+        base_time: Option<f64>,
+        options: LayerOptions,
+    ) -> Result<UpdateReport> {
+        let mut followed = None;
+        if options.mode == LayerMode::Follow
+            && !instance.is_active(base_id)
+            && self.resolve(base_id).is_ok()
+        {
+            followed = self
+                .play(instance, base_id.clone(), AlreadyPlaying::Continue)?
+                .map(|event| TransitionEvent {
+                    reason: TransitionReason::Followed,
+                    ..event
+                });
+        }
+
+        let mut report = self.try_update(instance, args, sprite)?;
+        if let Some(event) = followed {
+            report.transitions.insert(0, event);
+        }
+        if let Some(base_time) = base_time.filter(|_| options.sync_time) {
+            self.set_normalized_time(instance, base_time, sprite)?;
+        }
+        Ok(report)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
+/// The per-instance information necessary for running a `LayeredStateMachine`
+pub struct LayeredInstance<S, D> {
+    /// The instance of every layer, base layer first
+    pub layers: Vec<StateInstance<S, D>>,
+    /// The parameters used to evaluate the transitions of every layer
+    pub parameters: Parameters,
+}
+
+impl<S, D: StableHash> StableHash for LayeredInstance<S, D> {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        self.layers.stable_hash(checksum);
+        self.parameters.stable_hash(checksum);
+    }
+}
@@ -0,0 +1,26 @@
+use super::{StateID, StateIndex};
+
+#[derive(Debug, Default)]
+/// The machine's transitions and the states' own targets resolved to `StateIndex`es when it is built,
+/// so updates don't have to look anything up by ID
+pub(crate) struct Links {
+    /// The groups containing every state, outermost first
+    pub groups: Vec<Vec<StateID>>,
+    /// The transitions out of every state or its groups, in the order they were added, with the
+    /// state they enter if it exists
+    pub transitions: Vec<Vec<(usize, Option<StateIndex>)>>,
+    /// The any-state transitions allowed to leave every state, highest priority first, with the
+    /// state they enter if it exists
+    pub any_state_transitions: Vec<Vec<(usize, Option<StateIndex>)>>,
+    /// The states entered by every state's `AnimationState::targets`, in the same order, if they exist
+    pub next_states: Vec<Vec<Option<StateIndex>>>,
+    /// The first state or group every state can move to that does not exist, if any
+    pub missing_targets: Vec<Option<StateID>>,
+    /// The name of every parameter the conditions use, in the order of the slots given to new
+    /// instances, see `Parameters`
+    pub parameters: Vec<String>,
+    /// The slots of the parameters of every transition's conditions, in the same order
+    pub transition_parameters: Vec<Vec<usize>>,
+    /// The slots of the parameters of every any-state transition's conditions, in the same order
+    pub any_state_parameters: Vec<Vec<usize>>,
+}
@@ -0,0 +1,87 @@
+use serde::{Deserialize, Serialize};
+
+use super::StateIndex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// Where in a state's playback a `Marker` is placed
+pub enum MarkerPosition {
+    /// Whenever the playback reaches this index in the sprite sheet
+    Frame(usize),
+    /// Whenever the playback reaches this many milliseconds into a loop of the state
+    Time(f64),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A named point in a state's playback that is reported when the playback crosses it
+pub struct Marker {
+    /// The name reported when the marker is crossed
+    pub name: String,
+    /// Where the marker is placed
+    pub at: MarkerPosition,
+}
+
+impl Marker {
+    /// Creates a marker that is crossed whenever the sprite sheet index `index` is reached
+    pub fn frame(name: impl Into<String>, index: usize) -> Self {
+        Self {
+            name: name.into(),
+            at: MarkerPosition::Frame(index),
+        }
+    }
+
+    /// Creates a marker that is crossed whenever the playback reaches `ms` into a loop
+    pub fn time(name: impl Into<String>, ms: f64) -> Self {
+        Self {
+            name: name.into(),
+            at: MarkerPosition::Time(ms),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A marker that was crossed during an update
+pub struct MarkerEvent {
+    /// The state the marker belongs to, see `AnimationStateMachine::id_at` for its ID
+    pub state: StateIndex,
+    /// The name of the marker
+    pub name: String,
+}
+
+/// Adds the name of every frame marker on `index` to `crossed`
+pub(crate) fn cross_frame(markers: &[Marker], index: usize, crossed: &mut Vec<String>) {
+    for marker in markers {
+        if marker.at == MarkerPosition::Frame(index) {
+            crossed.push(marker.name.clone());
+        }
+    }
+}
+
+/// Adds the name of every time marker in `[from_ms, to_ms)` to `crossed`, once for each time it is crossed.
+/// * `loop_ms` If `Some` the length of a loop of the playback, otherwise the playback doesn't loop.
+pub(crate) fn cross_time(
+    markers: &[Marker],
+    from_ms: f64,
+    to_ms: f64,
+    loop_ms: Option<f64>,
+    crossed: &mut Vec<String>,
+) {
+    for marker in markers {
+        let MarkerPosition::Time(ms) = marker.at else {
+            continue;
+        };
+        match loop_ms {
+            Some(loop_ms) if loop_ms > 0. => {
+                let mut at = ms + ((from_ms - ms) / loop_ms).ceil().max(0.) * loop_ms;
+                while at < to_ms {
+                    crossed.push(marker.name.clone());
+                    at += loop_ms;
+                }
+            }
+            _ => {
+                if from_ms <= ms && ms < to_ms {
+                    crossed.push(marker.name.clone());
+                }
+            }
+        }
+    }
+}
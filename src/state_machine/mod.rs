@@ -1,17 +1,45 @@
-use core::marker::PhantomData;
-use std::{collections::HashMap, fmt::Debug};
+use core::{cmp::Reverse, marker::PhantomData};
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt::Debug,
+    sync::OnceLock,
+};
 
 use serde::{Deserialize, Serialize};
 
+use crate::error::{Error, Result, ValidationError};
+
+use self::links::Links;
+
+mod builder;
+mod checksum;
+mod layers;
+mod links;
+mod marker;
+mod parameters;
+mod rng;
 mod state_container;
+mod state_group;
 mod state_id;
 mod state_instance;
 mod traits;
+mod transition;
 
+pub use builder::AnimationStateMachineBuilder;
+pub use checksum::{Checksum, StableHash};
+pub use layers::{Layer, LayerMode, LayerOptions, LayeredInstance, LayeredStateMachine};
+pub(crate) use marker::{cross_frame, cross_time};
+pub use marker::{Marker, MarkerEvent, MarkerPosition};
+pub use parameters::{Parameter, Parameters};
+pub use rng::SeededRng;
 pub use state_container::StateContainer;
-pub use state_id::StateID;
-pub use state_instance::StateInstance;
+pub use state_group::StateGroup;
+pub use state_id::{StateID, StateIndex};
+pub use state_instance::{AlreadyPlaying, Crossfade, StateInstance};
 pub use traits::*;
+pub use transition::{
+    AnyStateTransition, Comparison, Condition, Transition, TransitionEvent, TransitionReason,
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 /// A finite state machine across animation states
@@ -19,6 +47,15 @@ pub struct AnimationStateMachine<Sprite, State, FrameSource> {
     frame_source: FrameSource,
     default_id: StateID,
     states: StateContainer<State>,
+    #[serde(default)]
+    groups: StateContainer<StateGroup>,
+    #[serde(default)]
+    transitions: Vec<Transition>,
+    #[serde(default)]
+    any_state_transitions: Vec<AnyStateTransition>,
+    /// Built on first use and dropped whenever the machine changes
+    #[serde(skip)]
+    links: OnceLock<Links>,
     #[serde(skip)]
     phantom: PhantomData<Sprite>,
 }
@@ -30,14 +67,9 @@ where
 {
     /// Creates a new FSM initialized with `default_id` and `default_state`
     pub fn with_default(frame_source: F, default_id: StateID, default_state: T) -> Self {
-        let mut states = HashMap::new();
+        let mut states = StateContainer::default();
         states.insert(default_id.clone(), default_state);
-        Self {
-            frame_source,
-            default_id,
-            states: states.into(),
-            phantom: PhantomData::default(),
-        }
+        Self::with_states(frame_source, default_id, states)
     }
 
     /// Creates a new FSM initialized with `default_id` and `default_state`
@@ -46,31 +78,800 @@ where
             frame_source,
             default_id,
             states,
-            phantom: PhantomData::default(),
+            groups: StateContainer::default(),
+            transitions: Vec::new(),
+            any_state_transitions: Vec::new(),
+            links: OnceLock::new(),
+            phantom: PhantomData,
         }
     }
 
     /// Add all given `StateID` `AnimationState` pairs to the FSM.
     pub fn add_states(&mut self, pairs: Vec<(StateID, T)>) {
         for (id, state) in pairs {
-            self.states.0.insert(id, state);
+            self.states.insert(id, state);
         }
+        self.links = OnceLock::new();
+    }
+
+    /// Add all given `StateID` `StateGroup` pairs to the FSM.
+    ///
+    /// Groups share their IDs with states, so transitions, `next_state`s and `play` can target them.
+    pub fn add_groups(&mut self, pairs: Vec<(StateID, StateGroup)>) {
+        for (id, group) in pairs {
+            self.groups.insert(id, group);
+        }
+        self.links = OnceLock::new();
+    }
+
+    /// Add conditional transitions to the FSM.
+    ///
+    /// Transitions are checked in the order they were added, the first one out of the current state
+    /// or one of the groups containing it whose conditions are all met is taken.
+    pub fn add_transitions(&mut self, transitions: Vec<Transition>) {
+        self.transitions.extend(transitions);
+        self.links = OnceLock::new();
+    }
+
+    /// Add transitions that can be taken from any state to the FSM.
+    ///
+    /// Any-state transitions take precedence over the regular transitions, see `AnyStateTransition`.
+    pub fn add_any_state_transitions(&mut self, transitions: Vec<AnyStateTransition>) {
+        self.any_state_transitions.extend(transitions);
+        self.links = OnceLock::new();
     }
 
     /// Run an update cycle for the FSM, potentially changing the frame or state
+    ///
+    /// Conditional transitions take precedence over the state's own `next_state`.
+    ///
+    /// # Panics
+    /// If the instance's current state or the state it moves to does not exist, see `try_update`.
     pub fn update(
         &self,
         instance: &mut StateInstance<T, T::Data>,
         args: UpdateArgs,
         sprite: &mut S,
+    ) -> UpdateReport {
+        self.try_update(instance, args, sprite)
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Run an update cycle for the FSM, potentially changing the frame or state
+    ///
+    /// Any-state transitions take precedence over conditional transitions, which take precedence over
+    /// the state's own `next_state`.
+    /// If the current state, a state it can move to or the queued state does not exist, the instance is
+    /// left untouched and the error is returned. If a state the instance moves to during the update
+    /// can move to a state that does not exist, the update stops once it is entered and the next
+    /// update returns the error.
+    /// `args.delta_ms` is scaled by the instance's speed, negative and non-finite deltas are treated as 0,
+    /// and nothing happens while it is paused.
+    /// When a state ends part way through the update, the rest of the time is played by the next state.
+    pub fn try_update(
+        &self,
+        instance: &mut StateInstance<T, T::Data>,
+        args: UpdateArgs,
+        sprite: &mut S,
+    ) -> Result<UpdateReport> {
+        self.update_with(instance, args, sprite, &mut Vec::new())
+    }
+
+    /// Run an update cycle for every instance with its sprite in `batch`, see `try_update`.
+    ///
+    /// The instances share their buffers, so once they have grown nothing is allocated unless a
+    /// transition is taken.
+    /// `on_report` is called with the position in `batch` of every instance that crossed a marker, took
+    /// a transition or failed to update.
+    pub fn update_batch(
+        &self,
+        batch: &mut [(StateInstance<T, T::Data>, S)],
+        args: UpdateArgs,
+        mut on_report: impl FnMut(usize, Result<UpdateReport>),
     ) {
-        let state = self.states.0.get(&instance.current_id).unwrap();
+        let mut markers = Vec::new();
+        for (i, (instance, sprite)) in batch.iter_mut().enumerate() {
+            match self.update_with(instance, args, sprite, &mut markers) {
+                Ok(report) if report.is_empty() => {}
+                result => on_report(i, result),
+            }
+        }
+    }
+
+    /// Run an update cycle for the FSM, collecting markers in `markers` which is left empty
+    fn update_with(
+        &self,
+        instance: &mut StateInstance<T, T::Data>,
+        args: UpdateArgs,
+        sprite: &mut S,
+        markers: &mut Vec<String>,
+    ) -> Result<UpdateReport> {
+        let index = self.locate(instance.index(), &instance.current_id)?;
+        instance.set_index(index);
+        // Checked before anything changes, so an update either fails untouched or runs to completion
+        self.check_targets(instance)?;
+        if instance.is_paused() {
+            return Ok(UpdateReport::default());
+        }
+        // Time never runs backwards, so like negative speeds, negative and non-finite deltas are 0
+        let delta_ms = args.delta_ms * instance.speed();
+        let delta_ms = if delta_ms.is_finite() {
+            delta_ms.max(0.)
+        } else {
+            0.
+        };
+
+        let mut report = UpdateReport::default();
+        if let Some(crossfade) = &mut instance.crossfade {
+            // The outgoing state is updated first so the current state ends up setting the sprite
+            if let Ok(from_state) = self.locate(crossfade.from_state, &crossfade.from_id) {
+                crossfade.from_state = from_state;
+                self.states[from_state].update(
+                    &mut crossfade.from_data,
+                    UpdateArgs { delta_ms },
+                    sprite,
+                    markers,
+                );
+                crossfade.from_index = sprite.get_index();
+                report
+                    .markers
+                    .extend(markers.drain(..).map(|name| MarkerEvent {
+                        state: from_state,
+                        name,
+                    }));
+            }
+            crossfade.elapsed_ms += delta_ms;
+            if crossfade.weight() >= 1. {
+                instance.crossfade = None;
+            }
+        }
+
+        let mut delta_ms = delta_ms;
+        let mut stalled = 0;
+        // Time left over when a state ends is played by the next state, so a large delta has the same
+        // result as many small ones
+        while let Some(carry_ms) = self.step(instance, delta_ms, sprite, &mut report, markers) {
+            if self.check_targets(instance).is_err() {
+                // Left for the next update to report, before it changes anything
+                break;
+            }
+            if carry_ms >= delta_ms {
+                // Stop chains of states that end without using up any time from cycling forever
+                stalled += 1;
+                if stalled > self.states.len() {
+                    break;
+                }
+            } else {
+                stalled = 0;
+            }
+            delta_ms = carry_ms;
+        }
+        Ok(report)
+    }
+
+    /// Fails if the instance's current state can move to a state that does not exist, or its queued
+    /// state does not exist
+    fn check_targets(&self, instance: &StateInstance<T, T::Data>) -> Result<()> {
+        let missing = self.links().missing_targets[instance.index().0 as usize]
+            .as_ref()
+            .or(instance
+                .queued
+                .as_ref()
+                .filter(|_| self.locate_queued(instance).is_none()));
+        match missing {
+            Some(to) => Err(Error::MissingTarget {
+                from: instance.current_id.clone(),
+                to: to.clone(),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Where the instance's queued state is, only hashing its ID if it was changed by hand after
+    /// `play_queued` resolved it
+    fn locate_queued(&self, instance: &StateInstance<T, T::Data>) -> Option<StateIndex> {
+        let queued = instance.queued.as_ref()?;
+        self.locate(instance.queued_index(), queued)
+            .ok()
+            .or_else(|| self.index_of(queued))
+    }
+
+    /// Updates the instance's current state by `delta_ms` and takes at most one transition.
+    /// Every state it can move to must exist, see `check_targets`.
+    ///
+    /// # Returns
+    /// The ms left over if the state moved on because its playback ended, which the next state should play
+    fn step(
+        &self,
+        instance: &mut StateInstance<T, T::Data>,
+        delta_ms: f64,
+        sprite: &mut S,
+        report: &mut UpdateReport,
+        markers: &mut Vec<String>,
+    ) -> Option<f64> {
+        let index = instance.index();
+        let state = &self.states[index];
+        state.update(&mut instance.data, UpdateArgs { delta_ms }, sprite, markers);
+        report.markers.extend(
+            markers
+                .drain(..)
+                .map(|name| MarkerEvent { state: index, name }),
+        );
+
+        let links = self.links();
+        // Transitions to missing states can't be taken, `check_targets` reports them
+        let transition = links.any_state_transitions[index.0 as usize]
+            .iter()
+            .filter_map(|(i, to)| {
+                Some((
+                    &self.any_state_transitions[*i],
+                    links.any_state_parameters[*i].as_slice(),
+                    (*to)?,
+                ))
+            })
+            .find(|(transition, positions, _)| {
+                transition::all_met_at(&transition.conditions, positions, &instance.parameters)
+            })
+            .map(|(transition, positions, to)| {
+                (
+                    to,
+                    (transition.conditions.as_slice(), positions),
+                    transition.crossfade_ms,
+                    TransitionReason::AnyState,
+                )
+            })
+            .or_else(|| {
+                links.transitions[index.0 as usize]
+                    .iter()
+                    .filter_map(|(i, to)| {
+                        Some((
+                            &self.transitions[*i],
+                            links.transition_parameters[*i].as_slice(),
+                            (*to)?,
+                        ))
+                    })
+                    .find(|(transition, positions, _)| {
+                        transition::all_met_at(
+                            &transition.conditions,
+                            positions,
+                            &instance.parameters,
+                        )
+                    })
+                    .map(|(transition, positions, to)| {
+                        (
+                            to,
+                            (transition.conditions.as_slice(), positions),
+                            transition.crossfade_ms,
+                            TransitionReason::Conditional,
+                        )
+                    })
+            });
+        // Everything was resolved when the machine was built or the request was queued
+        let (next_index, reason) = match transition {
+            Some((to, _, _, reason)) => (to, reason),
+            None if instance.queued.is_some() && state.reached_end(&instance.data) => {
+                let queued = self.locate_queued(instance)?;
+                instance.queued = None;
+                (queued, TransitionReason::Queued)
+            }
+            None => {
+                let next = state.next_state(&instance.data)?;
+                let next = links.next_states[index.0 as usize].get(next).copied();
+                (next.flatten()?, TransitionReason::Finished)
+            }
+        };
+        // Indices are only resolved from this machine's states, which are never removed
+        let (next_id, next_state) = self
+            .states
+            .entry_at(next_index)
+            .expect("resolved state index out of bounds");
+
+        report.transitions.push(TransitionEvent {
+            from: index,
+            to: next_index,
+            reason,
+        });
+        let carry_ms = matches!(
+            reason,
+            TransitionReason::Finished | TransitionReason::Queued
+        )
+        .then(|| state.overflow_ms(&instance.data));
+        let crossfade_ms = transition.map_or(0., |(_, _, crossfade_ms, _)| crossfade_ms);
+        let from = (crossfade_ms > 0.).then(|| {
+            (
+                instance.current_id.clone(),
+                instance.index(),
+                sprite.get_index(),
+            )
+        });
+        let data = next_state.start_with_rng(&mut instance.rng);
+        let from_data = instance.enter(
+            next_id,
+            next_index,
+            &links.groups[next_index.0 as usize],
+            data,
+        );
+        instance.crossfade = None;
+        if let Some((_, (conditions, positions), _, _)) = transition {
+            transition::consume_triggers_at(conditions, positions, &mut instance.parameters);
+        }
+        if let Some((from_id, from_state, from_index)) = from {
+            instance.crossfade = Some(Crossfade {
+                from_id,
+                from_state,
+                from_data,
+                from_index,
+                elapsed_ms: 0.,
+                duration_ms: crossfade_ms,
+            });
+        }
+        carry_ms
+    }
+
+    /// Immediately moves the instance to `state_id`, regardless of the machine's transitions.
+    /// If `state_id` is a group its default state is played.
+    ///
+    /// Unless the request is ignored, any request queued with `play_queued` and any ongoing crossfade
+    /// are dropped. Fails if `state_id` or the instance's current state does not exist.
+    ///
+    /// # Returns
+    /// * `Some(event)` describing the transition if the instance changed or restarted its state
+    /// * `None` if `state_id` was already active, see `StateInstance::is_active`, and `already_playing`
+    ///   is `Continue`
+    pub fn play(
+        &self,
+        instance: &mut StateInstance<T, T::Data>,
+        state_id: StateID,
+        already_playing: AlreadyPlaying,
+    ) -> Result<Option<TransitionEvent>> {
+        let requested = state_id;
+        let state_id = self.resolve(&requested)?;
+        let index = self.locate(StateIndex::default(), state_id)?;
+        let from = self.locate(instance.index(), &instance.current_id)?;
+        let state = &self.states[index];
+        if already_playing == AlreadyPlaying::Continue && instance.is_active(&requested) {
+            return Ok(None);
+        }
+        instance.queued = None;
+
+        let event = TransitionEvent {
+            from,
+            to: index,
+            reason: TransitionReason::Played,
+        };
+        let data = state.start_with_rng(&mut instance.rng);
+        instance.enter(
+            state_id,
+            index,
+            &self.links().groups[index.0 as usize],
+            data,
+        );
+        instance.crossfade = None;
+        Ok(Some(event))
+    }
+
+    /// Moves the instance to `state_id` once its current state reaches its end (or the end of a loop),
+    /// instead of the state's own `next_state`. Conditional transitions still take precedence.
+    ///
+    /// Replaces any previously queued request. If `state_id` is already active, see
+    /// `StateInstance::is_active`, and `already_playing` is `Continue` the request is ignored.
+    pub fn play_queued(
+        &self,
+        instance: &mut StateInstance<T, T::Data>,
+        state_id: StateID,
+        already_playing: AlreadyPlaying,
+    ) -> Result<()> {
+        let requested = state_id;
+        let state_id = self.resolve(&requested)?.clone();
+        let index = self.locate(StateIndex::default(), &state_id)?;
+        if already_playing == AlreadyPlaying::Continue && instance.is_active(&requested) {
+            return Ok(());
+        }
+        instance.queue(state_id, index);
+        Ok(())
+    }
+
+    /// The transitions resolved to `StateIndex`es, built the first time they are needed
+    fn links(&self) -> &Links {
+        self.links.get_or_init(|| self.link())
+    }
+
+    /// Resolves the transitions out of every state, and the states' own targets, to `StateIndex`es, and
+    /// the parameters of the transitions' conditions to slots
+    fn link(&self) -> Links {
+        let mut any_state: Vec<_> = (0..self.any_state_transitions.len()).collect();
+        // Stable, so transitions with the same priority stay in the order they were added
+        any_state.sort_by_key(|i| Reverse(self.any_state_transitions[*i].priority));
+
+        let mut links = Links::default();
+        for (id, state) in self.states.iter() {
+            let groups = self.groups_of(id);
+            let is_active = |other: &StateID| other == id || groups.contains(other);
+            links.transitions.push(
+                self.transitions
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, transition)| is_active(&transition.from))
+                    .map(|(i, transition)| (i, self.index_of(&transition.to)))
+                    .collect(),
+            );
+            links.any_state_transitions.push(
+                any_state
+                    .iter()
+                    .map(|i| (*i, &self.any_state_transitions[*i]))
+                    .filter(|(_, transition)| {
+                        let to = self.resolve(&transition.to).ok();
+                        !is_active(&transition.to)
+                            && !to.is_some_and(is_active)
+                            && !transition.except.iter().any(is_active)
+                    })
+                    .map(|(i, transition)| (i, self.index_of(&transition.to)))
+                    .collect(),
+            );
+            let index = links.groups.len();
+            let targets = state.targets();
+            let next_states: Vec<_> = targets.iter().map(|target| self.index_of(target)).collect();
+            let missing_target = targets
+                .into_iter()
+                .zip(&next_states)
+                .find(|(_, next_state)| next_state.is_none())
+                .map(|(target, _)| target)
+                .or_else(|| {
+                    links.transitions[index]
+                        .iter()
+                        .find(|(_, to)| to.is_none())
+                        .map(|(i, _)| self.transitions[*i].to.clone())
+                })
+                .or_else(|| {
+                    links.any_state_transitions[index]
+                        .iter()
+                        .find(|(_, to)| to.is_none())
+                        .map(|(i, _)| self.any_state_transitions[*i].to.clone())
+                });
+            links.missing_targets.push(missing_target);
+            links.next_states.push(next_states);
+            links.groups.push(groups);
+        }
+
+        let mut slot = |condition: &Condition| {
+            let name = condition.name();
+            match links
+                .parameters
+                .iter()
+                .position(|parameter| parameter == name)
+            {
+                Some(position) => position,
+                None => {
+                    links.parameters.push(name.to_string());
+                    links.parameters.len() - 1
+                }
+            }
+        };
+        let transition_parameters: Vec<Vec<usize>> = self
+            .transitions
+            .iter()
+            .map(|transition| transition.conditions.iter().map(&mut slot).collect())
+            .collect();
+        let any_state_parameters: Vec<Vec<usize>> = self
+            .any_state_transitions
+            .iter()
+            .map(|transition| transition.conditions.iter().map(&mut slot).collect())
+            .collect();
+        links.transition_parameters = transition_parameters;
+        links.any_state_parameters = any_state_parameters;
+        links
+    }
+
+    /// The ID of the state at `index`, e.g. to name the states of a `TransitionEvent` or `MarkerEvent`
+    pub fn id_at(&self, index: StateIndex) -> Option<&StateID> {
+        self.states.id_at(index)
+    }
+
+    /// The index of the state entered when `id` is targeted, following the defaults of groups
+    pub fn index_of(&self, id: &StateID) -> Option<StateIndex> {
+        self.resolve(id)
+            .ok()
+            .and_then(|id| self.states.index_of(id))
+    }
+
+    /// The index of the state `id`, only hashing `id` if `index` doesn't point at it.
+    /// Instances keep both so that they still work after their ID was changed by hand, or with a
+    /// machine they weren't created by.
+    fn locate(&self, index: StateIndex, id: &StateID) -> Result<StateIndex> {
+        if self.states.id_at(index) == Some(id) {
+            return Ok(index);
+        }
+        self.states
+            .index_of(id)
+            .ok_or_else(|| Error::MissingState(id.clone()))
+    }
+
+    /// The instance's current state
+    fn current_state(&self, instance: &StateInstance<T, T::Data>) -> Result<&T> {
+        let index = self.locate(instance.index(), &instance.current_id)?;
+        Ok(&self.states[index])
+    }
+
+    /// How far through its playback the instance's current state is, see `AnimationState::normalized_time`
+    pub fn normalized_time(&self, instance: &StateInstance<T, T::Data>) -> Option<f64> {
+        self.current_state(instance)
+            .ok()?
+            .normalized_time(&instance.data)
+    }
 
-        state.update(&mut instance.data, args, sprite);
+    /// Moves the playback of the instance's current state to `normalized_time` (from 0 to 1) and shows
+    /// the frame there on `sprite`, see `AnimationState::set_normalized_time`
+    ///
+    /// A `normalized_time` that is infinite or NaN is ignored, leaving the playback where it is.
+    pub fn set_normalized_time(
+        &self,
+        instance: &mut StateInstance<T, T::Data>,
+        normalized_time: f64,
+        sprite: &mut S,
+    ) -> Result<()> {
+        let state = self.current_state(instance)?;
+        if !normalized_time.is_finite() {
+            return Ok(());
+        }
+        state.set_normalized_time(&mut instance.data, normalized_time, sprite);
+        Ok(())
+    }
 
-        if let Some(next_id) = state.next_state(&instance.data) {
-            *instance = self.new_instance(next_id).unwrap();
+    /// The total length of the instance's current state in ms, see `AnimationState::total_duration_ms`
+    pub fn total_duration_ms(&self, instance: &StateInstance<T, T::Data>) -> Option<f64> {
+        self.current_state(instance)
+            .ok()?
+            .total_duration_ms(&instance.data)
+    }
+
+    /// How long the instance's current state has been playing in ms, see `AnimationState::elapsed_ms`
+    pub fn elapsed_ms(&self, instance: &StateInstance<T, T::Data>) -> Option<f64> {
+        self.current_state(instance)
+            .ok()?
+            .elapsed_ms(&instance.data)
+    }
+
+    /// How long until the instance's current state ends in ms, see `AnimationState::remaining_ms`
+    pub fn remaining_ms(&self, instance: &StateInstance<T, T::Data>) -> Option<f64> {
+        self.current_state(instance)
+            .ok()?
+            .remaining_ms(&instance.data)
+    }
+
+    /// Moves the playback of the instance's current state to `ms` after it started and shows the
+    /// frame there on `sprite`, see `AnimationState::seek`
+    ///
+    /// No markers are reported and no transitions are taken until the next update. An `ms` that is
+    /// infinite or NaN is ignored, leaving the playback where it is.
+    pub fn seek(
+        &self,
+        instance: &mut StateInstance<T, T::Data>,
+        ms: f64,
+        sprite: &mut S,
+    ) -> Result<()> {
+        let state = self.current_state(instance)?;
+        if ms.is_finite() {
+            state.seek(&mut instance.data, ms, sprite);
         }
+        Ok(())
+    }
+
+    /// Moves the playback of the instance's current state to `clock_ms` into its looping playback and
+    /// shows the frame there on `sprite`.
+    ///
+    /// Instances synced to the same clock animate in lockstep, offsetting `clock_ms` shifts their phase.
+    /// A `clock_ms` that is infinite or NaN is ignored, like in `seek`.
+    pub fn sync_to_clock(
+        &self,
+        instance: &mut StateInstance<T, T::Data>,
+        clock_ms: f64,
+        sprite: &mut S,
+    ) -> Result<()> {
+        let state = self.current_state(instance)?;
+        if !clock_ms.is_finite() {
+            return Ok(());
+        }
+        if let Some(length_ms) = state.length_ms() {
+            let normalized_time = clock_ms.rem_euclid(length_ms) / length_ms;
+            state.set_normalized_time(&mut instance.data, normalized_time, sprite);
+        }
+        Ok(())
+    }
+
+    /// The ID of the state entered when `id` is targeted, following the defaults of groups
+    pub fn resolve<'a>(&'a self, id: &'a StateID) -> Result<&'a StateID> {
+        let mut resolved = id;
+        for _ in 0..=self.groups.len() {
+            match self.groups.get(resolved) {
+                Some(group) => resolved = &group.default_id,
+                None if self.states.contains(resolved) => return Ok(resolved),
+                None => return Err(Error::MissingState(resolved.clone())),
+            }
+        }
+        Err(Error::Invalid(vec![ValidationError::GroupCycle(
+            id.clone(),
+        )]))
+    }
+
+    /// The group directly containing the state or group `id`
+    pub fn parent(&self, id: &StateID) -> Option<&StateID> {
+        self.groups
+            .iter()
+            .find(|(_, group)| group.members.contains(id))
+            .map(|(group_id, _)| group_id)
+    }
+
+    /// The groups containing the state or group `id`, outermost first
+    pub fn groups_of(&self, id: &StateID) -> Vec<StateID> {
+        let mut groups = Vec::new();
+        let mut current = id;
+        while let Some(parent) = self.parent(current) {
+            if parent == id || groups.len() >= self.groups.len() {
+                // A cycle, which `validate` reports
+                break;
+            }
+            groups.push(parent.clone());
+            current = parent;
+        }
+        groups.reverse();
+        groups
+    }
+
+    /// Checks the FSM for problems that would otherwise only show up at runtime.
+    /// * `frame_count` If `Some` the number of frames available in the frame source.
+    ///
+    /// # Returns
+    /// * `Ok(warnings)` if the FSM can run, with any harmless problems such as unreachable states
+    /// * `Err(Error::Invalid(problems))` with every problem found otherwise
+    pub fn validate(&self, frame_count: Option<usize>) -> Result<Vec<ValidationError>> {
+        let mut problems = Vec::new();
+
+        if !self.contains(&self.default_id) {
+            problems.push(ValidationError::MissingDefaultState(
+                self.default_id.clone(),
+            ));
+        }
+
+        let mut states: Vec<_> = self.states.iter().collect();
+        states.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+        let ids: Vec<_> = states.iter().map(|(id, _)| *id).collect();
+        for (id, state) in states {
+            problems.extend(state.validate(id, frame_count));
+            for target in state.targets() {
+                if !self.contains(&target) {
+                    problems.push(ValidationError::MissingTarget {
+                        from: (*id).clone(),
+                        to: target,
+                    });
+                }
+            }
+        }
+
+        for transition in &self.transitions {
+            if !self.contains(&transition.from) {
+                problems.push(ValidationError::MissingSource(transition.from.clone()));
+            }
+            if !self.contains(&transition.to) {
+                problems.push(ValidationError::MissingTarget {
+                    from: transition.from.clone(),
+                    to: transition.to.clone(),
+                });
+            }
+        }
+
+        for transition in &self.any_state_transitions {
+            if !self.contains(&transition.to) {
+                problems.push(ValidationError::MissingAnyStateTarget(
+                    transition.to.clone(),
+                ));
+            }
+        }
+
+        problems.extend(self.validate_groups());
+
+        let reachable = self.reachable_states();
+        problems.extend(
+            ids.into_iter()
+                .filter(|id| !reachable.contains(id))
+                .map(|id| ValidationError::Unreachable(id.clone())),
+        );
+
+        if problems.iter().any(ValidationError::is_fatal) {
+            Err(Error::Invalid(problems))
+        } else {
+            Ok(problems)
+        }
+    }
+
+    /// The problems with the FSM's groups
+    fn validate_groups(&self) -> Vec<ValidationError> {
+        let mut problems = Vec::new();
+        let mut groups: Vec<_> = self.groups.iter().collect();
+        groups.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+        let mut members = HashSet::new();
+        for (id, group) in groups {
+            if self.states.contains(id) {
+                problems.push(ValidationError::DuplicateId(id.clone()));
+            }
+            if !group.members.contains(&group.default_id) {
+                problems.push(ValidationError::InvalidGroupDefault {
+                    group: id.clone(),
+                    default: group.default_id.clone(),
+                });
+            }
+            for member in &group.members {
+                if !self.contains(member) {
+                    problems.push(ValidationError::MissingMember {
+                        group: id.clone(),
+                        member: member.clone(),
+                    });
+                }
+                if !members.insert(member) {
+                    problems.push(ValidationError::DuplicateMember(member.clone()));
+                }
+            }
+            let mut ancestor = self.parent(id);
+            for _ in 0..self.groups.len() {
+                match ancestor {
+                    Some(parent) if parent == id => {
+                        problems.push(ValidationError::GroupCycle(id.clone()));
+                        break;
+                    }
+                    Some(parent) => ancestor = self.parent(parent),
+                    None => break,
+                }
+            }
+        }
+        problems
+    }
+
+    /// The IDs of every state that can be reached from the default state
+    fn reachable_states(&self) -> HashSet<&StateID> {
+        let mut reachable = HashSet::new();
+        let mut queue = VecDeque::from([&self.default_id]);
+        if self.contains(&self.default_id) {
+            queue.extend(
+                self.any_state_transitions
+                    .iter()
+                    .map(|transition| &transition.to),
+            );
+        }
+        while let Some(id) = queue.pop_front() {
+            if let Some((id, group)) = self.groups.get_key_value(id) {
+                if reachable.insert(id) {
+                    queue.push_back(&group.default_id);
+                }
+                continue;
+            }
+            let Some((id, state)) = self.states.get_key_value(id) else {
+                continue;
+            };
+            if !reachable.insert(id) {
+                continue;
+            }
+            for target in state.targets() {
+                if let Some((target, _)) = self.states.get_key_value(&target) {
+                    queue.push_back(target);
+                } else if let Some((target, _)) = self.groups.get_key_value(&target) {
+                    queue.push_back(target);
+                }
+            }
+            let groups = self.groups_of(id);
+            queue.extend(
+                self.transitions
+                    .iter()
+                    .filter(|transition| {
+                        &transition.from == id || groups.contains(&transition.from)
+                    })
+                    .map(|transition| &transition.to),
+            );
+        }
+        reachable
+    }
+
+    /// Whether `id` is the ID of one of the FSM's states or groups
+    fn contains(&self, id: &StateID) -> bool {
+        self.states.contains(id) || self.groups.contains(id)
     }
 
     /// The ASMs frame source
@@ -79,17 +880,70 @@ where
     }
 
     /// Creates a new instance from the default state
+    ///
+    /// # Panics
+    /// If the default state does not exist, see `try_default_instance`.
     pub fn default_instance(&self) -> StateInstance<T, T::Data> {
-        let state = self.states.0.get(&self.default_id).unwrap();
-        StateInstance::new(self.default_id.clone(), state.start())
+        self.try_default_instance()
+            .unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Creates a new instance from the default state
+    pub fn try_default_instance(&self) -> Result<StateInstance<T, T::Data>> {
+        if !self.contains(&self.default_id) {
+            return Err(Error::MissingDefaultState(self.default_id.clone()));
+        }
+        self.try_new_instance(self.default_id.clone())
     }
 
     /// Creates a new instance from the given state id if it exists
     pub fn new_instance(&self, instance_id: StateID) -> Option<StateInstance<T, T::Data>> {
+        self.try_new_instance(instance_id).ok()
+    }
+
+    /// Creates a new instance from the given state id, or the default state of the given group id
+    pub fn try_new_instance(&self, instance_id: StateID) -> Result<StateInstance<T, T::Data>> {
+        self.instance_with_rng(instance_id, SeededRng::unique())
+    }
+
+    /// Creates a new instance from the default state whose random choices are determined by `seed`
+    pub fn try_default_instance_seeded(&self, seed: u64) -> Result<StateInstance<T, T::Data>> {
+        if !self.contains(&self.default_id) {
+            return Err(Error::MissingDefaultState(self.default_id.clone()));
+        }
+        self.try_new_instance_seeded(self.default_id.clone(), seed)
+    }
+
+    /// Creates a new instance from the given state id whose random choices are determined by `seed`
+    pub fn try_new_instance_seeded(
+        &self,
+        instance_id: StateID,
+        seed: u64,
+    ) -> Result<StateInstance<T, T::Data>> {
+        self.instance_with_rng(instance_id, SeededRng::new(seed))
+    }
+
+    fn instance_with_rng(
+        &self,
+        instance_id: StateID,
+        mut rng: SeededRng,
+    ) -> Result<StateInstance<T, T::Data>> {
+        let instance_id = self.resolve(&instance_id)?;
+        let index = self.locate(StateIndex::default(), instance_id)?;
+        let data = self.states[index].start_with_rng(&mut rng);
+        let mut instance = StateInstance::new(instance_id.clone(), data)
+            .with_rng(rng)
+            .with_groups(self.links().groups[index.0 as usize].clone());
+        instance.set_index(index);
+        instance.parameters = Parameters::with_names(&self.links().parameters);
+        Ok(instance)
+    }
+
+    /// The state with the given ID
+    pub fn get_state(&self, id: &StateID) -> Result<&T> {
         self.states
-            .0
-            .get(&instance_id)
-            .map(|state| StateInstance::new(instance_id, state.start()))
+            .get(id)
+            .ok_or_else(|| Error::MissingState(id.clone()))
     }
 
     /// The ASMs default state ID
@@ -101,11 +955,47 @@ where
     pub fn states(&self) -> &StateContainer<T> {
         &self.states
     }
+
+    /// The ASMs groups of states
+    pub fn groups(&self) -> &StateContainer<StateGroup> {
+        &self.groups
+    }
+
+    /// The ASMs conditional transitions
+    pub fn transitions(&self) -> &[Transition] {
+        &self.transitions
+    }
+
+    /// The ASMs transitions that can be taken from any state
+    pub fn any_state_transitions(&self) -> &[AnyStateTransition] {
+        &self.any_state_transitions
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 /// Arguments used when updating the `AnimationStateMachine`
 pub struct UpdateArgs {
     /// The number of ms elapsed since the last update was called
     pub delta_ms: f64,
 }
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// Everything of note that happened during an update of the `AnimationStateMachine`
+pub struct UpdateReport {
+    /// The markers crossed during the update, grouped by state in the order the states were played,
+    /// starting with the state being faded out.
+    ///
+    /// Within a state, its time markers come before its frame markers rather than in the order they
+    /// were crossed, and a marker crossed more than once appears that many times in a row. Split large
+    /// deltas into smaller updates if the order within a state matters.
+    pub markers: Vec<MarkerEvent>,
+    /// The transitions taken during the update, in the order they were taken
+    pub transitions: Vec<TransitionEvent>,
+}
+
+impl UpdateReport {
+    /// Whether nothing of note happened during the update
+    pub fn is_empty(&self) -> bool {
+        self.markers.is_empty() && self.transitions.is_empty()
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// A single value that gameplay code can set to drive an instance's transitions
+pub enum Parameter {
+    /// A value that stays set until it is changed
+    Bool(bool),
+    /// A floating point value, i.e. a speed
+    Float(f64),
+    /// An integer value, i.e. a health total
+    Int(i64),
+    /// A flag that stays set until a transition that depends on it is taken
+    Trigger(bool),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(
+    from = "HashMap<String, Parameter>",
+    into = "HashMap<String, Parameter>"
+)]
+/// The named parameters of a single `StateInstance`
+///
+/// Every parameter has a slot, so that conditions can find it by position instead of by name.
+/// Instances created by an `AnimationStateMachine` start with a slot for every parameter its
+/// conditions use, in the order the machine resolved them.
+pub struct Parameters {
+    /// The name of every parameter with its value, if it has been set
+    slots: Vec<(String, Option<Parameter>)>,
+}
+
+impl Parameters {
+    /// Creates parameters with an unset slot for every name, in order
+    pub(crate) fn with_names(names: &[String]) -> Self {
+        Self {
+            slots: names.iter().map(|name| (name.clone(), None)).collect(),
+        }
+    }
+
+    /// Sets the parameter `name` to `value`, adding a slot for it if it has none
+    fn set(&mut self, name: impl Into<String>, value: Parameter) {
+        let name = name.into();
+        match self.position(&name) {
+            Some(position) => self.slots[position].1 = Some(value),
+            None => self.slots.push((name, Some(value))),
+        }
+    }
+
+    /// The position of the slot of the parameter `name`
+    fn position(&self, name: &str) -> Option<usize> {
+        self.slots.iter().position(|(slot, _)| slot == name)
+    }
+
+    /// Sets the bool parameter `name` to `value`
+    pub fn set_bool(&mut self, name: impl Into<String>, value: bool) {
+        self.set(name, Parameter::Bool(value));
+    }
+
+    /// Sets the float parameter `name` to `value`
+    pub fn set_float(&mut self, name: impl Into<String>, value: f64) {
+        self.set(name, Parameter::Float(value));
+    }
+
+    /// Sets the int parameter `name` to `value`
+    pub fn set_int(&mut self, name: impl Into<String>, value: i64) {
+        self.set(name, Parameter::Int(value));
+    }
+
+    /// Sets the trigger `name`, it will be reset once a transition consumes it
+    pub fn set_trigger(&mut self, name: impl Into<String>) {
+        self.set(name, Parameter::Trigger(true));
+    }
+
+    /// Resets the trigger `name` without taking a transition
+    pub fn reset_trigger(&mut self, name: &str) {
+        if let Some(position) = self.position(name) {
+            self.reset_trigger_at(position, name);
+        }
+    }
+
+    /// Resets the trigger `name`, whose slot is expected at `position`
+    pub(crate) fn reset_trigger_at(&mut self, position: usize, name: &str) {
+        let Some(position) = self.locate(position, name) else {
+            return;
+        };
+        if let Some(Parameter::Trigger(set)) = &mut self.slots[position].1 {
+            *set = false;
+        }
+    }
+
+    /// The parameter `name` if it has been set
+    pub fn get(&self, name: &str) -> Option<&Parameter> {
+        self.slots[self.position(name)?].1.as_ref()
+    }
+
+    /// The parameter `name` if it has been set, only comparing names if its slot isn't at `position`
+    pub(crate) fn get_at(&self, position: usize, name: &str) -> Option<&Parameter> {
+        self.slots[self.locate(position, name)?].1.as_ref()
+    }
+
+    /// The position of the slot of `name`, checking `position` first.
+    /// The slots of parameters that were set by hand, or of parameters moved between the instances of
+    /// different machines, may not be where the machine expects them.
+    fn locate(&self, position: usize, name: &str) -> Option<usize> {
+        match self.slots.get(position) {
+            Some((slot, _)) if slot == name => Some(position),
+            _ => self.position(name),
+        }
+    }
+
+    /// The value of the bool parameter `name` if it has been set to a bool
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        match self.get(name) {
+            Some(Parameter::Bool(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The value of the float parameter `name` if it has been set to a float
+    pub fn get_float(&self, name: &str) -> Option<f64> {
+        match self.get(name) {
+            Some(Parameter::Float(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The value of the int parameter `name` if it has been set to an int
+    pub fn get_int(&self, name: &str) -> Option<i64> {
+        match self.get(name) {
+            Some(Parameter::Int(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Whether the trigger `name` is currently set
+    pub fn is_triggered(&self, name: &str) -> bool {
+        matches!(self.get(name), Some(Parameter::Trigger(true)))
+    }
+
+    /// Every parameter that has been set, with its name, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Parameter)> {
+        self.slots
+            .iter()
+            .filter_map(|(name, value)| Some((name.as_str(), value.as_ref()?)))
+    }
+}
+
+impl From<HashMap<String, Parameter>> for Parameters {
+    fn from(parameters: HashMap<String, Parameter>) -> Self {
+        Self {
+            slots: parameters
+                .into_iter()
+                .map(|(name, value)| (name, Some(value)))
+                .collect(),
+        }
+    }
+}
+
+impl From<Parameters> for HashMap<String, Parameter> {
+    fn from(parameters: Parameters) -> Self {
+        parameters
+            .slots
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value?)))
+            .collect()
+    }
+}
@@ -0,0 +1,63 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Checksum, StableHash};
+
+/// The seed of the next generator created by `SeededRng::unique`
+static NEXT_UNIQUE_SEED: AtomicU64 = AtomicU64::new(0x5EED);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A small, seedable random number generator (SplitMix64).
+///
+/// Its whole state is serialized, so a restored generator continues the same sequence.
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    /// Creates a generator whose sequence is determined by `seed`
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Creates a generator with a different seed than every other generator created this way, so
+    /// instances created without an explicit seed don't all make the same choices
+    pub fn unique() -> Self {
+        Self::new(SeededRng::new(NEXT_UNIQUE_SEED.fetch_add(1, Ordering::Relaxed)).next_u64())
+    }
+
+    /// The next random `u64`
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// The next random `f64` in `[0, 1)`
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A random `f64` in `[min, max)`, or `min` if the range is empty
+    pub fn range_f64(&mut self, min: f64, max: f64) -> f64 {
+        if max <= min {
+            return min;
+        }
+        min + self.next_f64() * (max - min)
+    }
+}
+
+impl Default for SeededRng {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl StableHash for SeededRng {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        self.state.stable_hash(checksum);
+    }
+}
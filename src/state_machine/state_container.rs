@@ -1,16 +1,139 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Index};
 
 use serde::{Deserialize, Serialize};
 
-use super::state_id::StateID;
+use super::state_id::{StateID, StateIndex};
 
 #[derive(Debug, Clone)]
-/// A convenience wrapper to hold an ASM's states with their IDs
-pub struct StateContainer<S>(pub HashMap<StateID, S>);
+/// A convenience wrapper to hold an ASM's states with their IDs.
+///
+/// Every entry gets a `StateIndex` in the order it was added, so it can be looked up without hashing.
+/// Replacing an entry keeps its index.
+pub struct StateContainer<S> {
+    entries: Vec<(StateID, S)>,
+    indices: HashMap<StateID, StateIndex>,
+}
+
+impl<S> StateContainer<S> {
+    /// Adds `value` with the given ID, replacing the entry that already has it
+    ///
+    /// # Returns
+    /// The index of the entry
+    pub fn insert(&mut self, id: StateID, value: S) -> StateIndex {
+        if let Some(index) = self.indices.get(&id) {
+            self.entries[index.0 as usize].1 = value;
+            return *index;
+        }
+        let index = StateIndex(self.entries.len() as u32);
+        self.indices.insert(id.clone(), index);
+        self.entries.push((id, value));
+        index
+    }
+
+    /// The entry with the given ID
+    pub fn get(&self, id: &StateID) -> Option<&S> {
+        self.index_of(id).and_then(|index| self.get_index(index))
+    }
+
+    /// The entry with the given ID, along with the ID as it is stored
+    pub fn get_key_value(&self, id: &StateID) -> Option<(&StateID, &S)> {
+        self.index_of(id).and_then(|index| self.entry_at(index))
+    }
+
+    /// The index of the entry with the given ID
+    pub fn index_of(&self, id: &StateID) -> Option<StateIndex> {
+        self.indices.get(id).copied()
+    }
+
+    /// The entry at `index`
+    pub fn get_index(&self, index: StateIndex) -> Option<&S> {
+        self.entries.get(index.0 as usize).map(|(_, value)| value)
+    }
+
+    /// The entry at `index`, along with its ID
+    pub fn entry_at(&self, index: StateIndex) -> Option<(&StateID, &S)> {
+        self.entries
+            .get(index.0 as usize)
+            .map(|(id, value)| (id, value))
+    }
+
+    /// The ID of the entry at `index`
+    pub fn id_at(&self, index: StateIndex) -> Option<&StateID> {
+        self.entries.get(index.0 as usize).map(|(id, _)| id)
+    }
+
+    /// Whether there is an entry with the given ID
+    pub fn contains(&self, id: &StateID) -> bool {
+        self.indices.contains_key(id)
+    }
+
+    /// The number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether there are no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Every entry with its ID, in index order
+    pub fn iter(&self) -> impl Iterator<Item = (&StateID, &S)> {
+        self.entries.iter().map(|(id, value)| (id, value))
+    }
+
+    /// Every ID, in index order
+    pub fn ids(&self) -> impl Iterator<Item = &StateID> {
+        self.entries.iter().map(|(id, _)| id)
+    }
+}
+
+impl<S> Index<StateIndex> for StateContainer<S> {
+    type Output = S;
+
+    /// The entry at `index`
+    ///
+    /// # Panics
+    /// If there is no entry at `index`, see `get_index`.
+    fn index(&self, index: StateIndex) -> &S {
+        &self.entries[index.0 as usize].1
+    }
+}
 
 impl<S> From<HashMap<StateID, S>> for StateContainer<S> {
     fn from(value: HashMap<StateID, S>) -> Self {
-        StateContainer(value)
+        // Sorted so the indices don't depend on the map's iteration order
+        let mut entries: Vec<_> = value.into_iter().collect();
+        entries.sort_unstable_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+        entries.into_iter().collect()
+    }
+}
+
+impl<S> Default for StateContainer<S> {
+    fn default() -> Self {
+        StateContainer {
+            entries: Vec::new(),
+            indices: HashMap::new(),
+        }
+    }
+}
+
+impl<S> FromIterator<(StateID, S)> for StateContainer<S> {
+    fn from_iter<I: IntoIterator<Item = (StateID, S)>>(iter: I) -> Self {
+        let mut container = StateContainer::default();
+        for (id, value) in iter {
+            container.insert(id, value);
+        }
+        container
+    }
+}
+
+impl<S> IntoIterator for StateContainer<S> {
+    type Item = (StateID, S);
+    type IntoIter = std::vec::IntoIter<(StateID, S)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
     }
 }
 
@@ -22,7 +145,7 @@ where
     where
         S: serde::Serializer,
     {
-        self.0.iter().collect::<Vec<_>>().serialize(serializer)
+        self.entries.serialize(serializer)
     }
 }
 
@@ -34,11 +157,7 @@ where
     where
         D: serde::Deserializer<'de>,
     {
-        let as_vec = Vec::deserialize(deserializer)?;
-        let mut map = HashMap::with_capacity(as_vec.len());
-        for (key, value) in as_vec {
-            map.insert(key, value);
-        }
-        Ok(StateContainer(map))
+        let as_vec: Vec<(StateID, State)> = Vec::deserialize(deserializer)?;
+        Ok(as_vec.into_iter().collect())
     }
 }
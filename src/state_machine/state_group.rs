@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+use super::StateID;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A named group of states and other groups, behaving like a nested state machine.
+///
+/// Targeting the group enters its default member, and transitions leaving the group are checked
+/// while any of its members is active.
+pub struct StateGroup {
+    /// The member entered when the group itself is targeted
+    pub default_id: StateID,
+    /// The IDs of the states and groups inside this group
+    pub members: Vec<StateID>,
+}
+
+impl StateGroup {
+    /// Creates a group of `members` that is entered through `default_id`
+    pub fn new(default_id: StateID, members: Vec<StateID>) -> Self {
+        Self {
+            default_id,
+            members,
+        }
+    }
+}
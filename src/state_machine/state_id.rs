@@ -1,8 +1,10 @@
+use std::fmt::Display;
+
 #[cfg(feature = "bevy")]
 use bevy::prelude::{Component, Reflect};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "bevy", derive(Component, Reflect))]
 /// The ID of a state in the state machine
 pub struct StateID(pub String);
@@ -12,3 +14,22 @@ impl From<String> for StateID {
         StateID(value)
     }
 }
+
+impl From<&str> for StateID {
+    fn from(value: &str) -> Self {
+        StateID(value.to_string())
+    }
+}
+
+impl Display for StateID {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+/// The position of a state in its `AnimationStateMachine`, resolved from its `StateID` when the
+/// machine is built so the state can be found without hashing its ID
+pub struct StateIndex(pub u32);
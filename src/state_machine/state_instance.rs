@@ -4,19 +4,90 @@ use core::marker::PhantomData;
 use bevy::prelude::Component;
 use serde::{Deserialize, Serialize};
 
-use super::{AnimationState, StateID};
+use super::{AnimationState, Checksum, Parameters, SeededRng, StableHash, StateID, StateIndex};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// What to do when asked to play the state an instance is already in
+pub enum AlreadyPlaying {
+    /// Start the state over from the beginning
+    #[default]
+    Restart,
+    /// Keep playing the state from where it is
+    Continue,
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "bevy", derive(Component))]
 /// The per-instance information necessary for running the ASM
+///
+/// Cloning an instance allocates, as its IDs, groups and parameters are copied along with its data.
 pub struct StateInstance<S, D> {
     /// This instance's ID
     pub current_id: StateID,
+    /// Where `current_id` is in the machine, so it can be found without hashing it
+    #[serde(default)]
+    index: StateIndex,
+    /// The groups containing the current state, outermost first
+    #[serde(default)]
+    groups: Vec<StateID>,
     /// The state's data for this instance
     pub data: D,
+    /// The parameters used to evaluate the ASM's transitions for this instance
+    pub parameters: Parameters,
+    /// The state to play once the current state reaches its end, see `AnimationStateMachine::play_queued`
+    #[serde(default)]
+    pub queued: Option<StateID>,
+    /// Where `queued` is in the machine, so it can be found without hashing it
+    #[serde(default)]
+    queued_index: StateIndex,
+    /// How fast the playback runs, 1 being normal speed
+    #[serde(default = "default_speed")]
+    speed: f64,
+    /// Whether updates are ignored until the instance is resumed
+    #[serde(default)]
+    paused: bool,
+    /// The state that is fading out after a transition with a crossfade
+    #[serde(default = "Option::default")]
+    pub crossfade: Option<Crossfade<D>>,
+    /// The generator used by the instance's states to make random choices
+    #[serde(default)]
+    pub rng: SeededRng,
     phantom: PhantomData<S>,
 }
 
+impl<S, D: Clone> Clone for StateInstance<S, D> {
+    fn clone(&self) -> Self {
+        Self {
+            current_id: self.current_id.clone(),
+            index: self.index,
+            groups: self.groups.clone(),
+            data: self.data.clone(),
+            parameters: self.parameters.clone(),
+            queued: self.queued.clone(),
+            queued_index: self.queued_index,
+            speed: self.speed,
+            paused: self.paused,
+            crossfade: self.crossfade.clone(),
+            rng: self.rng.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<S, D: StableHash> StableHash for StateInstance<S, D> {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        self.current_id.stable_hash(checksum);
+        self.groups.stable_hash(checksum);
+        self.data.stable_hash(checksum);
+        self.parameters.stable_hash(checksum);
+        self.queued.stable_hash(checksum);
+        self.speed.stable_hash(checksum);
+        self.paused.stable_hash(checksum);
+        self.crossfade.stable_hash(checksum);
+        self.rng.stable_hash(checksum);
+    }
+}
+
 impl<S, D> StateInstance<S, D>
 where
     S: AnimationState<Data = D>,
@@ -25,8 +96,152 @@ where
     pub fn new(state_id: StateID, data: D) -> Self {
         Self {
             current_id: state_id,
+            index: StateIndex::default(),
+            groups: Vec::new(),
             data,
-            phantom: PhantomData::default(),
+            parameters: Parameters::default(),
+            queued: None,
+            queued_index: StateIndex::default(),
+            speed: 1.,
+            paused: false,
+            crossfade: None,
+            rng: SeededRng::unique(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Stops the playback until `resume` is called
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Continues the playback after a `pause`
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Whether the playback is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Sets how fast the playback runs, e.g. 0.5 for slow motion or 2 for double speed.
+    /// Negative speeds are treated as 0.
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed.max(0.);
+    }
+
+    /// How fast the playback runs, 1 being normal speed
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// The IDs of the active groups, outermost first, followed by the current state's ID
+    pub fn path(&self) -> impl Iterator<Item = &StateID> {
+        self.groups.iter().chain(core::iter::once(&self.current_id))
+    }
+
+    /// Whether `id` is the current state or one of the groups containing it
+    pub fn is_active(&self, id: &StateID) -> bool {
+        self.current_id == *id || self.groups.contains(id)
+    }
+
+    /// Where the current state is in the machine
+    pub fn index(&self) -> StateIndex {
+        self.index
+    }
+
+    /// Sets where the current state is in the machine
+    pub(crate) fn set_index(&mut self, index: StateIndex) {
+        self.index = index;
+    }
+
+    /// Where the queued state is in the machine
+    pub(crate) fn queued_index(&self) -> StateIndex {
+        self.queued_index
+    }
+
+    /// Queues `state_id` at `index`, see `AnimationStateMachine::play_queued`
+    pub(crate) fn queue(&mut self, state_id: StateID, index: StateIndex) {
+        self.queued = Some(state_id);
+        self.queued_index = index;
+    }
+
+    /// Sets the generator used by the instance's states to make random choices
+    pub(crate) fn with_rng(mut self, rng: SeededRng) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    /// Sets the groups containing the current state, outermost first
+    pub(crate) fn with_groups(mut self, groups: Vec<StateID>) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// Switches this instance to `state_id` at `index` inside `groups`, keeping its parameters.
+    /// The instance's buffers are reused, so switching between states with short IDs doesn't allocate.
+    ///
+    /// # Returns
+    /// The data of the state that was left
+    pub(crate) fn enter(
+        &mut self,
+        state_id: &StateID,
+        index: StateIndex,
+        groups: &[StateID],
+        data: D,
+    ) -> D {
+        self.current_id.0.clone_from(&state_id.0);
+        self.index = index;
+        self.groups.truncate(groups.len());
+        let kept = self.groups.len();
+        for (group, id) in self.groups.iter_mut().zip(groups) {
+            group.0.clone_from(&id.0);
+        }
+        self.groups.extend_from_slice(&groups[kept..]);
+        core::mem::replace(&mut self.data, data)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A state that keeps playing while it fades out over the instance's current state
+pub struct Crossfade<D> {
+    /// The state being faded out
+    pub from_id: StateID,
+    /// Where `from_id` is in the machine, so it can be found without hashing it
+    #[serde(default)]
+    pub from_state: StateIndex,
+    /// The data of the state being faded out
+    pub from_data: D,
+    /// The sprite index the state being faded out is showing
+    pub from_index: usize,
+    /// How long the crossfade has run for, in ms
+    pub elapsed_ms: f64,
+    /// How long the crossfade lasts, in ms
+    pub duration_ms: f64,
+}
+
+impl<D> Crossfade<D> {
+    /// How far along the crossfade is, from 0 (only the old state is visible) to 1 (only the
+    /// current state is visible)
+    pub fn weight(&self) -> f64 {
+        if self.duration_ms <= 0. {
+            return 1.;
         }
+        (self.elapsed_ms / self.duration_ms).clamp(0., 1.)
+    }
+}
+
+impl<D: StableHash> StableHash for Crossfade<D> {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        self.from_id.stable_hash(checksum);
+        self.from_data.stable_hash(checksum);
+        self.from_index.stable_hash(checksum);
+        self.elapsed_ms.stable_hash(checksum);
+        self.duration_ms.stable_hash(checksum);
     }
 }
+
+fn default_speed() -> f64 {
+    1.
+}
@@ -1,6 +1,7 @@
 use std::fmt::Debug;
 
-use super::{StateID, UpdateArgs};
+use super::{SeededRng, StateID, UpdateArgs};
+use crate::error::ValidationError;
 
 /// The types of states that can be represented by the AnimationStateMachine
 pub trait AnimationState: Debug + Send + Sync {
@@ -12,14 +13,122 @@ pub trait AnimationState: Debug + Send + Sync {
     /// Called when the state machine starts processing this state (used for reseting any stateful fields)
     fn start(&self) -> Self::Data;
 
+    /// Like `start`, for states that make random choices when they start.
+    ///
+    /// The state machine always starts states through this method, with the instance's generator.
+    fn start_with_rng(&self, _rng: &mut SeededRng) -> Self::Data {
+        self.start()
+    }
+
     /// Update the given sprite according to the behavior of this state.
-    fn update(&self, data: &mut Self::Data, args: UpdateArgs, sprite: &mut Self::Sprite);
+    ///
+    /// The name of every marker crossed during the update is added to `markers`.
+    fn update(
+        &self,
+        data: &mut Self::Data,
+        args: UpdateArgs,
+        sprite: &mut Self::Sprite,
+        markers: &mut Vec<String>,
+    );
 
-    /// Queries for the ID of the next state in the state machine.
+    /// Queries for the next state in the state machine.
     /// # Returns
     /// * `None` if the state machine should continue processing this state
-    /// * `Some(id)` if the state machine should stop processing this state and move to `id`
-    fn next_state(&self, data: &Self::Data) -> Option<StateID>;
+    /// * `Some(position)` if the state machine should stop processing this state and move to the state
+    ///   at `position` in `targets`, which the machine resolved when it was built
+    fn next_state(&self, data: &Self::Data) -> Option<usize>;
+
+    /// Whether the playback reached its end, or the end of a loop, during the last update.
+    ///
+    /// Used to decide when requests queued with `AnimationStateMachine::play_queued` are taken.
+    fn reached_end(&self, _data: &Self::Data) -> bool {
+        false
+    }
+
+    /// The length of the state's playback in ms up to where it ends, see `total_duration_ms`, or of a
+    /// single loop for states that loop forever.
+    ///
+    /// `None` if the state has no length.
+    fn length_ms(&self) -> Option<f64> {
+        None
+    }
+
+    /// How far through its playback the state is, from 0 to 1, measured over `length_ms`.
+    /// A state that ends reaches 1 when it ends, a state that loops forever restarts from 0 every loop.
+    ///
+    /// `None` if the state has no length.
+    fn normalized_time(&self, _data: &Self::Data) -> Option<f64> {
+        None
+    }
+
+    /// Moves the playback to `normalized_time` (from 0 to 1) through the state, or through the
+    /// current loop for states that loop forever, and shows the frame there on `sprite`.
+    ///
+    /// Does nothing if the state has no length.
+    fn set_normalized_time(
+        &self,
+        _data: &mut Self::Data,
+        _normalized_time: f64,
+        _sprite: &mut Self::Sprite,
+    ) {
+    }
+
+    /// The total length of the state's playback in ms, including any delay before it starts.
+    ///
+    /// `None` if the state loops forever or has no length.
+    fn total_duration_ms(&self, _data: &Self::Data) -> Option<f64> {
+        None
+    }
+
+    /// The number of ms the state has been playing for, including any delay before it started.
+    ///
+    /// `None` if the state doesn't track its playback time.
+    fn elapsed_ms(&self, _data: &Self::Data) -> Option<f64> {
+        None
+    }
+
+    /// The number of ms until the state's playback ends, or until the current loop ends for states
+    /// that loop forever.
+    fn remaining_ms(&self, data: &Self::Data) -> Option<f64> {
+        match (self.total_duration_ms(data), self.elapsed_ms(data)) {
+            (Some(total_ms), Some(elapsed_ms)) => Some((total_ms - elapsed_ms).max(0.)),
+            _ => Some(self.length_ms()? * (1. - self.normalized_time(data)?)),
+        }
+    }
+
+    /// Moves the playback to `ms` after the state started and shows the frame there on `sprite`.
+    ///
+    /// By default this is done through `set_normalized_time`, wrapping around for looping states.
+    fn seek(&self, data: &mut Self::Data, ms: f64, sprite: &mut Self::Sprite) {
+        let Some(length_ms) = self.length_ms() else {
+            return;
+        };
+        let loops = ms / length_ms;
+        let normalized_time = if self.total_duration_ms(data).is_some() {
+            loops.min(1.)
+        } else {
+            loops.rem_euclid(1.)
+        };
+        self.set_normalized_time(data, normalized_time, sprite);
+    }
+
+    /// The number of ms of the last update that were left over after the playback reached its end,
+    /// which are played by the next state when this state moves on.
+    fn overflow_ms(&self, _data: &Self::Data) -> f64 {
+        0.
+    }
+
+    /// The IDs of every state this state can move to on its own, in the same order every time as
+    /// `next_state` refers to them by position
+    fn targets(&self) -> Vec<StateID> {
+        Vec::new()
+    }
+
+    /// Checks this state for problems, `id` is the ID this state is stored under.
+    /// * `frame_count` If `Some` the number of frames available in the frame source.
+    fn validate(&self, _id: &StateID, _frame_count: Option<usize>) -> Vec<ValidationError> {
+        Vec::new()
+    }
 }
 
 /// The types that an `AnimationStateMachine` can animate
@@ -0,0 +1,301 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Parameter, Parameters, StateID, StateIndex};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// How a numeric parameter is compared against a condition's value
+pub enum Comparison {
+    /// `parameter < value`
+    Less,
+    /// `parameter <= value`
+    LessOrEqual,
+    /// `parameter > value`
+    Greater,
+    /// `parameter >= value`
+    GreaterOrEqual,
+    /// `parameter == value`
+    Equal,
+    /// `parameter != value`
+    NotEqual,
+}
+
+impl Comparison {
+    fn compare<T: PartialOrd>(&self, parameter: T, value: T) -> bool {
+        match self {
+            Comparison::Less => parameter < value,
+            Comparison::LessOrEqual => parameter <= value,
+            Comparison::Greater => parameter > value,
+            Comparison::GreaterOrEqual => parameter >= value,
+            Comparison::Equal => parameter == value,
+            Comparison::NotEqual => parameter != value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A check against a single parameter of a `StateInstance`
+///
+/// Conditions on parameters that have not been set, or were set to a different type, are never met.
+pub enum Condition {
+    /// Met when the bool parameter `name` equals `value`
+    Bool {
+        /// The parameter's name
+        name: String,
+        /// The value the parameter must have
+        value: bool,
+    },
+    /// Met when the float parameter `name` compares to `value` using `comparison`
+    Float {
+        /// The parameter's name
+        name: String,
+        /// How the parameter is compared to `value`
+        comparison: Comparison,
+        /// The value the parameter is compared to
+        value: f64,
+    },
+    /// Met when the int parameter `name` compares to `value` using `comparison`
+    Int {
+        /// The parameter's name
+        name: String,
+        /// How the parameter is compared to `value`
+        comparison: Comparison,
+        /// The value the parameter is compared to
+        value: i64,
+    },
+    /// Met when the trigger `name` is set
+    Trigger(String),
+}
+
+impl Condition {
+    /// The name of the parameter this condition checks
+    pub fn name(&self) -> &str {
+        match self {
+            Condition::Bool { name, .. }
+            | Condition::Float { name, .. }
+            | Condition::Int { name, .. }
+            | Condition::Trigger(name) => name,
+        }
+    }
+
+    /// Whether this condition holds for the given parameters
+    pub fn is_met(&self, parameters: &Parameters) -> bool {
+        self.is_met_by(parameters.get(self.name()))
+    }
+
+    /// Whether this condition holds for the value of its parameter, `None` if it hasn't been set
+    fn is_met_by(&self, parameter: Option<&Parameter>) -> bool {
+        match (self, parameter) {
+            (Condition::Bool { value, .. }, Some(Parameter::Bool(parameter))) => parameter == value,
+            (
+                Condition::Float {
+                    comparison, value, ..
+                },
+                Some(Parameter::Float(parameter)),
+            ) => comparison.compare(parameter, value),
+            (
+                Condition::Int {
+                    comparison, value, ..
+                },
+                Some(Parameter::Int(parameter)),
+            ) => comparison.compare(parameter, value),
+            (Condition::Trigger(_), Some(Parameter::Trigger(set))) => *set,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A declarative transition from one state to another, taken when all of its conditions are met
+pub struct Transition {
+    /// The state this transition leaves
+    pub from: StateID,
+    /// The state this transition enters
+    pub to: StateID,
+    /// The conditions that must all be met for the transition to be taken.
+    /// A transition without conditions is taken on the first update of `from`.
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    /// How long `from` keeps playing and fades out over `to` after the transition is taken, in ms.
+    /// 0 switches instantly.
+    #[serde(default)]
+    pub crossfade_ms: f64,
+}
+
+impl Transition {
+    /// Creates a transition from `from` to `to` without any conditions
+    pub fn new(from: StateID, to: StateID) -> Self {
+        Self {
+            from,
+            to,
+            conditions: Vec::new(),
+            crossfade_ms: 0.,
+        }
+    }
+
+    /// Sets how long `from` keeps playing and fades out over `to` after the transition is taken, in ms
+    pub fn with_crossfade(mut self, crossfade_ms: f64) -> Self {
+        self.crossfade_ms = crossfade_ms;
+        self
+    }
+
+    /// Adds `condition` to the conditions that must be met for this transition
+    pub fn when(mut self, condition: Condition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    /// Whether all of this transition's conditions are met
+    pub fn is_satisfied(&self, parameters: &Parameters) -> bool {
+        all_met(&self.conditions, parameters)
+    }
+
+    /// Resets every trigger this transition depends on
+    pub fn consume_triggers(&self, parameters: &mut Parameters) {
+        consume_triggers(&self.conditions, parameters);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// A transition that can be taken from any state, e.g. into a "hurt" or "dead" state.
+///
+/// Any-state transitions are checked before the regular transitions, and never taken while their
+/// target is already active.
+pub struct AnyStateTransition {
+    /// The state or group this transition enters
+    pub to: StateID,
+    /// The conditions that must all be met for the transition to be taken
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    /// Transitions with a higher priority are checked first.
+    /// Transitions with the same priority are checked in the order they were added.
+    #[serde(default)]
+    pub priority: i32,
+    /// The states and groups this transition can not be taken from
+    #[serde(default)]
+    pub except: Vec<StateID>,
+    /// How long the state being left keeps playing and fades out over `to`, in ms
+    #[serde(default)]
+    pub crossfade_ms: f64,
+}
+
+impl AnyStateTransition {
+    /// Creates a transition from any state to `to` without any conditions
+    pub fn new(to: StateID) -> Self {
+        Self {
+            to,
+            conditions: Vec::new(),
+            priority: 0,
+            except: Vec::new(),
+            crossfade_ms: 0.,
+        }
+    }
+
+    /// Adds `condition` to the conditions that must be met for this transition
+    pub fn when(mut self, condition: Condition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    /// Sets the priority of this transition, higher priorities are checked first
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Prevents this transition from being taken while `id` is active
+    pub fn except(mut self, id: StateID) -> Self {
+        self.except.push(id);
+        self
+    }
+
+    /// Sets how long the state being left keeps playing and fades out over `to`, in ms
+    pub fn with_crossfade(mut self, crossfade_ms: f64) -> Self {
+        self.crossfade_ms = crossfade_ms;
+        self
+    }
+
+    /// Whether all of this transition's conditions are met
+    pub fn is_satisfied(&self, parameters: &Parameters) -> bool {
+        all_met(&self.conditions, parameters)
+    }
+
+    /// Resets every trigger this transition depends on
+    pub fn consume_triggers(&self, parameters: &mut Parameters) {
+        consume_triggers(&self.conditions, parameters);
+    }
+}
+
+/// Whether every condition holds for the given parameters
+fn all_met(conditions: &[Condition], parameters: &Parameters) -> bool {
+    conditions
+        .iter()
+        .all(|condition| condition.is_met(parameters))
+}
+
+/// Whether every condition holds for the given parameters, whose slots the machine resolved to
+/// `positions` in the same order as the conditions, see `Parameters`
+pub(crate) fn all_met_at(
+    conditions: &[Condition],
+    positions: &[usize],
+    parameters: &Parameters,
+) -> bool {
+    conditions
+        .iter()
+        .zip(positions)
+        .all(|(condition, position)| {
+            condition.is_met_by(parameters.get_at(*position, condition.name()))
+        })
+}
+
+/// Resets every trigger the conditions depend on
+fn consume_triggers(conditions: &[Condition], parameters: &mut Parameters) {
+    for condition in conditions {
+        if let Condition::Trigger(name) = condition {
+            parameters.reset_trigger(name);
+        }
+    }
+}
+
+/// Resets every trigger the conditions depend on, whose slots the machine resolved to `positions`
+pub(crate) fn consume_triggers_at(
+    conditions: &[Condition],
+    positions: &[usize],
+    parameters: &mut Parameters,
+) {
+    for (condition, position) in conditions.iter().zip(positions) {
+        if let Condition::Trigger(name) = condition {
+            parameters.reset_trigger_at(*position, name);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Why the `AnimationStateMachine` moved from one state to another
+pub enum TransitionReason {
+    /// The state finished and named its `next_state`
+    Finished,
+    /// The conditions of one of the machine's `Transition`s were met
+    Conditional,
+    /// The conditions of one of the machine's `AnyStateTransition`s were met
+    AnyState,
+    /// The state was requested with `AnimationStateMachine::play`
+    Played,
+    /// The state was requested with `AnimationStateMachine::play_queued` and the previous state reached its end
+    Queued,
+    /// The layer followed its base layer into the state, see `LayerMode::Follow`
+    Followed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A transition that was taken during an update
+///
+/// The states are reported by index so updates don't copy their IDs, see
+/// `AnimationStateMachine::id_at`.
+pub struct TransitionEvent {
+    /// The state that was left
+    pub from: StateIndex,
+    /// The state that was entered
+    pub to: StateIndex,
+    /// Why the transition was taken
+    pub reason: TransitionReason,
+}
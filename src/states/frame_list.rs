@@ -0,0 +1,436 @@
+use core::marker::PhantomData;
+
+#[cfg(feature = "bevy")]
+use bevy::{prelude::Component, reflect::TypePath};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::ValidationError,
+    state_machine::{
+        cross_frame, cross_time, AnimationState, Checksum, IndexSprite, Marker, Sprite, StableHash,
+        StateID, UpdateArgs,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// A single entry in a `FrameListState`
+pub struct Frame {
+    /// The index in the sprite sheet to show
+    pub index: usize,
+    /// The number of milliseconds the frame stays on screen for
+    pub duration_ms: f64,
+}
+
+impl From<(usize, f64)> for Frame {
+    fn from((index, duration_ms): (usize, f64)) -> Self {
+        Frame { index, duration_ms }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "FrameListStateFields")]
+#[cfg_attr(feature = "bevy", derive(TypePath))]
+/// A state that plays an explicit list of frames, each with its own duration.
+///
+/// Unlike `IndexState` the frames do not have to be contiguous in the sprite sheet and may repeat.
+pub struct FrameListState<Sprite> {
+    /// The frames in the order they are played
+    frames: Vec<Frame>,
+    /// The state to switch to after the last frame has been shown for its full duration
+    /// If `None` loop on this state indefinitely.
+    next_state: Option<StateID>,
+    /// The named points in the playback that are reported when crossed
+    #[serde(default)]
+    markers: Vec<Marker>,
+    /// The total number of milliseconds it takes to play every frame once
+    #[serde(skip_serializing)]
+    duration_ms: f64,
+    /// The position of the first frame shown for a negative or infinite amount of time, or NaN
+    #[serde(skip_serializing)]
+    invalid_frame: Option<usize>,
+    #[serde(skip)]
+    phantom: PhantomData<Sprite>,
+}
+
+#[derive(Deserialize)]
+/// The serialized settings of a `FrameListState`
+struct FrameListStateFields {
+    frames: Vec<Frame>,
+    next_state: Option<StateID>,
+    #[serde(default)]
+    markers: Vec<Marker>,
+}
+
+impl<S> From<FrameListStateFields> for FrameListState<S> {
+    fn from(fields: FrameListStateFields) -> Self {
+        FrameListState::new(fields.frames, fields.next_state).with_markers(fields.markers)
+    }
+}
+
+impl<S> FrameListState<S> {
+    /// Make a new frame list state
+    /// * `frames` The sprite sheet indices to show, in order, with how long each is shown for in milliseconds.
+    /// * `next_state` If `Some` the state to switch to after the last frame, otherwise loop on this state.
+    pub fn new(frames: Vec<impl Into<Frame>>, next_state: Option<StateID>) -> Self {
+        let frames: Vec<Frame> = frames.into_iter().map(Into::into).collect();
+        // The frames never change, so they are only walked once
+        let duration_ms = frames.iter().map(|frame| frame.duration_ms).sum();
+        let invalid_frame = frames
+            .iter()
+            .position(|frame| !frame.duration_ms.is_finite() || frame.duration_ms < 0.);
+        Self {
+            frames,
+            next_state,
+            markers: Vec::new(),
+            duration_ms,
+            invalid_frame,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Sets the named points in the playback that are reported when crossed
+    pub fn with_markers(mut self, markers: Vec<Marker>) -> Self {
+        self.markers = markers;
+        self
+    }
+
+    /// The named points in the playback that are reported when crossed
+    pub fn markers(&self) -> &[Marker] {
+        &self.markers
+    }
+
+    /// The frames in the order they are played
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    /// The total number of milliseconds it takes to play every frame once
+    pub fn duration_ms(&self) -> f64 {
+        self.duration_ms
+    }
+
+    /// Starts building a frame list state, see `FrameListStateBuilder`
+    pub fn builder() -> FrameListStateBuilder<S> {
+        FrameListStateBuilder::default()
+    }
+
+    fn advance(&self, data: &mut FrameListData<S>, delta_ms: f64, markers: &mut Vec<String>) {
+        let duration_ms = self.duration_ms;
+        if duration_ms <= 0. || self.invalid_frame.is_some() {
+            // Every frame would be skipped immediately, or a duration that `validate` reports would
+            // never be used up, so there is nothing to play
+            data.reached_end = true;
+            data.overflow_ms = delta_ms;
+            return;
+        }
+        if self.next_state.is_none() && data.ms_elapsed >= duration_ms {
+            // Skip whole loops up front so large deltas don't spin through every frame
+            if !self.markers.is_empty() {
+                let loops = (data.ms_elapsed / duration_ms).floor() as usize;
+                for _ in 0..loops {
+                    for frame in &self.frames {
+                        cross_frame(&self.markers, frame.index, markers);
+                    }
+                }
+            }
+            data.ms_elapsed %= duration_ms;
+            data.reached_end = true;
+        }
+        if self.next_state.is_none() {
+            // Measured from the first time the playback looped during this update
+            let from_ms = data.playback_ms - delta_ms;
+            let first_loop_ms = ((from_ms / duration_ms).floor() + 1.) * duration_ms;
+            if data.playback_ms >= first_loop_ms {
+                data.overflow_ms = data.playback_ms - first_loop_ms;
+            }
+        }
+
+        loop {
+            let duration_ms = self.frames[data.position].duration_ms;
+            if data.ms_elapsed < duration_ms {
+                break;
+            }
+            if data.position + 1 == self.frames.len() {
+                data.reached_end = true;
+                if self.next_state.is_some() {
+                    // Hold the last frame until the state machine moves on
+                    data.overflow_ms = data.ms_elapsed - duration_ms;
+                    break;
+                }
+                data.position = 0;
+            } else {
+                data.position += 1;
+            }
+            data.ms_elapsed -= duration_ms;
+            cross_frame(&self.markers, self.frames[data.position].index, markers);
+        }
+    }
+}
+
+impl<S> AnimationState for FrameListState<S>
+where
+    S: Send + Sync + Sprite + IndexSprite,
+{
+    type Sprite = S;
+    type Data = FrameListData<S>;
+
+    fn start(&self) -> Self::Data {
+        FrameListData::new()
+    }
+
+    fn update(
+        &self,
+        data: &mut Self::Data,
+        args: UpdateArgs,
+        sprite: &mut Self::Sprite,
+        markers: &mut Vec<String>,
+    ) {
+        data.reached_end = false;
+        data.overflow_ms = 0.;
+        if self.frames.is_empty() {
+            return;
+        }
+        if !data.started {
+            data.started = true;
+            cross_frame(&self.markers, self.frames[0].index, markers);
+        }
+
+        let loop_ms = self.next_state.is_none().then_some(self.duration_ms);
+        let playback_ms = data.playback_ms + args.delta_ms;
+        cross_time(
+            &self.markers,
+            data.playback_ms,
+            playback_ms,
+            loop_ms,
+            markers,
+        );
+        data.playback_ms = playback_ms;
+
+        data.ms_elapsed += args.delta_ms;
+        self.advance(data, args.delta_ms, markers);
+        sprite.set_index(self.frames[data.position].index);
+    }
+
+    fn next_state(&self, data: &Self::Data) -> Option<usize> {
+        // `next_state` is the only target
+        (self.next_state.is_some() && data.reached_end).then_some(0)
+    }
+
+    fn reached_end(&self, data: &Self::Data) -> bool {
+        data.reached_end
+    }
+
+    fn length_ms(&self) -> Option<f64> {
+        let duration_ms = self.duration_ms;
+        (duration_ms > 0.).then_some(duration_ms)
+    }
+
+    fn normalized_time(&self, data: &Self::Data) -> Option<f64> {
+        let duration_ms = self.duration_ms;
+        if duration_ms <= 0. {
+            return None;
+        }
+        Some(match self.next_state {
+            None => (data.playback_ms % duration_ms) / duration_ms,
+            Some(_) => (data.playback_ms / duration_ms).min(1.),
+        })
+    }
+
+    fn set_normalized_time(
+        &self,
+        data: &mut Self::Data,
+        normalized_time: f64,
+        sprite: &mut Self::Sprite,
+    ) {
+        let duration_ms = self.duration_ms;
+        if duration_ms <= 0. {
+            return;
+        }
+        let mut ms = normalized_time.clamp(0., 1.) * duration_ms;
+        // Stay in the current loop so the playback keeps counting up
+        let loops = match self.next_state {
+            None => (data.playback_ms / duration_ms).floor(),
+            Some(_) => 0.,
+        };
+        data.playback_ms = loops * duration_ms + ms;
+        data.position = 0;
+        while data.position + 1 < self.frames.len() && ms >= self.frames[data.position].duration_ms
+        {
+            ms -= self.frames[data.position].duration_ms;
+            data.position += 1;
+        }
+        data.ms_elapsed = ms;
+        sprite.set_index(self.frames[data.position].index);
+    }
+
+    fn overflow_ms(&self, data: &Self::Data) -> f64 {
+        data.overflow_ms
+    }
+
+    fn total_duration_ms(&self, _data: &Self::Data) -> Option<f64> {
+        self.next_state.as_ref().map(|_| self.duration_ms)
+    }
+
+    fn elapsed_ms(&self, data: &Self::Data) -> Option<f64> {
+        Some(data.playback_ms)
+    }
+
+    fn targets(&self) -> Vec<StateID> {
+        self.next_state.iter().cloned().collect()
+    }
+
+    fn validate(&self, id: &StateID, frame_count: Option<usize>) -> Vec<ValidationError> {
+        let mut problems = Vec::new();
+        if self.frames.is_empty() {
+            problems.push(ValidationError::NoFrames(id.clone()));
+        }
+        if let Some(position) = self.invalid_frame {
+            problems.push(ValidationError::InvalidFrameDuration {
+                state: id.clone(),
+                position,
+            });
+        }
+        if let Some(frame_count) = frame_count {
+            if let Some(index) = self
+                .frames
+                .iter()
+                .map(|frame| frame.index)
+                .filter(|index| *index >= frame_count)
+                .max()
+            {
+                problems.push(ValidationError::FrameOutOfBounds {
+                    state: id.clone(),
+                    index,
+                    frame_count,
+                });
+            }
+        }
+        problems
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Builds a `FrameListState` step by step, reporting bad settings as errors instead of panicking
+pub struct FrameListStateBuilder<Sprite> {
+    frames: Vec<Frame>,
+    next_state: Option<StateID>,
+    markers: Vec<Marker>,
+    phantom: PhantomData<Sprite>,
+}
+
+impl<S> Default for FrameListStateBuilder<S> {
+    fn default() -> Self {
+        Self {
+            frames: Vec::new(),
+            next_state: None,
+            markers: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<S> FrameListStateBuilder<S> {
+    /// Adds a frame showing `index` in the sprite sheet for `duration_ms`
+    pub fn frame(mut self, index: usize, duration_ms: impl Into<f64>) -> Self {
+        self.frames.push(Frame {
+            index,
+            duration_ms: duration_ms.into(),
+        });
+        self
+    }
+
+    /// Sets the state to switch to after the last frame, instead of looping
+    pub fn then(mut self, next_state: impl Into<StateID>) -> Self {
+        self.next_state = Some(next_state.into());
+        self
+    }
+
+    /// Adds a named point in the playback that is reported when crossed
+    pub fn marker(mut self, marker: Marker) -> Self {
+        self.markers.push(marker);
+        self
+    }
+
+    /// Builds the state, which is called `id` in the errors
+    pub fn build(self, id: &StateID) -> Result<FrameListState<S>, ValidationError> {
+        if self.frames.is_empty() {
+            return Err(ValidationError::NoFrames(id.clone()));
+        }
+        let state = FrameListState::new(self.frames, self.next_state).with_markers(self.markers);
+        match state.invalid_frame {
+            Some(position) => Err(ValidationError::InvalidFrameDuration {
+                state: id.clone(),
+                position,
+            }),
+            None => Ok(state),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
+/// The per-instance data of a `FrameListState`
+pub struct FrameListData<Sprite> {
+    /// The position of the current frame in the state's frame list
+    pub position: usize,
+    /// The number of milliseconds the current frame has been shown for
+    pub ms_elapsed: f64,
+    /// The total number of milliseconds the playback has run for
+    #[serde(default)]
+    pub playback_ms: f64,
+    reached_end: bool,
+    /// The ms of the last update left over after the playback reached its end
+    #[serde(default)]
+    overflow_ms: f64,
+    #[serde(default)]
+    started: bool,
+    #[serde(skip)]
+    phantom: PhantomData<Sprite>,
+}
+
+impl<S> FrameListData<S> {
+    /// Creates the data for the first frame of a `FrameListState`
+    pub fn new() -> Self {
+        Self {
+            position: 0,
+            ms_elapsed: 0.,
+            playback_ms: 0.,
+            reached_end: false,
+            overflow_ms: 0.,
+            started: false,
+            phantom: PhantomData,
+        }
+    }
+}
+
+// Not derived, which would require the sprite to be `Clone`
+impl<S> Clone for FrameListData<S> {
+    fn clone(&self) -> Self {
+        Self {
+            position: self.position,
+            ms_elapsed: self.ms_elapsed,
+            playback_ms: self.playback_ms,
+            reached_end: self.reached_end,
+            overflow_ms: self.overflow_ms,
+            started: self.started,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<S> StableHash for FrameListData<S> {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        self.position.stable_hash(checksum);
+        self.ms_elapsed.stable_hash(checksum);
+        self.playback_ms.stable_hash(checksum);
+        self.reached_end.stable_hash(checksum);
+        self.overflow_ms.stable_hash(checksum);
+        self.started.stable_hash(checksum);
+    }
+}
+
+impl<S> Default for FrameListData<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
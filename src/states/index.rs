@@ -1,42 +1,160 @@
-use core::marker::PhantomData;
+use core::{marker::PhantomData, ops::RangeInclusive};
 
 #[cfg(feature = "bevy")]
 use bevy::{prelude::Component, reflect::TypePath};
 use serde::{Deserialize, Serialize};
 
-use crate::state_machine::{AnimationState, IndexSprite, Sprite, StateID};
+use crate::{
+    error::ValidationError,
+    state_machine::{
+        cross_frame, cross_time, AnimationState, Checksum, IndexSprite, Marker, SeededRng, Sprite,
+        StableHash, StateID, UpdateArgs,
+    },
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// The order in which an `IndexState` plays its frames
+pub enum PlaybackMode {
+    /// Play from `min_i` to `max_i`, then loop back to `min_i`
+    #[default]
+    Forward,
+    /// Play from `max_i` to `min_i`, then loop back to `max_i`
+    Reverse,
+    /// Play from `min_i` to `max_i` and back down to `min_i`, then loop
+    PingPong,
+    /// Play from `min_i` to `max_i` once, then hold `max_i`
+    Once,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// How fast an `IndexState` plays its frames, ignoring fluidity
+pub enum FrameRate {
+    /// The number of frames shown per second
+    Fps(f64),
+    /// The number of milliseconds each frame is shown for
+    Mspf(f64),
+    /// The number of milliseconds the playback lasts, see `AnimationState::total_duration_ms`: until
+    /// the state moves on to its next state (every loop included) or holds its last frame with
+    /// `PlaybackMode::Once`, or a single loop if it loops forever
+    Duration(f64),
+}
+
+impl FrameRate {
+    /// The number of milliseconds each frame is shown for, when a playback lasts `positions` frames
+    pub fn mspf(self, positions: usize) -> f64 {
+        match self {
+            FrameRate::Fps(fps) => 1000. / fps,
+            FrameRate::Mspf(mspf) => mspf,
+            FrameRate::Duration(duration_ms) => duration_ms / positions.max(1) as f64,
+        }
+    }
+
+    /// Whether the frame rate is positive and finite
+    pub fn is_valid(self) -> bool {
+        let (FrameRate::Fps(value) | FrameRate::Mspf(value) | FrameRate::Duration(value)) = self;
+        value.is_finite() && value > 0.
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "IndexStateFields")]
 #[cfg_attr(feature = "bevy", derive(TypePath))]
-
 /// A state that determines the frame based on an incrementing index
+///
+/// Only the settings are serialized, the frame durations are derived from them when deserializing.
 pub struct IndexState<Sprite> {
     min_i: usize,
     max_i: usize,
-    /// The average framerate of the animation (ignoring fluidity)
+    /// How fast the frames are played
+    frame_rate: FrameRate,
+    /// The average number of milliseconds each frame is shown for (ignoring fluidity)
+    #[serde(skip_serializing)]
     nominal_mspf: f64,
     /// The number of milliseconds each frame should stay on screen for
+    #[serde(skip_serializing)]
     actual_mspf: f64,
-    /// The state to switch to after reading the max sprite index
-    /// If `None` loop on this state indefinitely.
+    /// The state to switch to after reaching the end of the playback `loops` times
+    /// If `None` loop on this state indefinitely (or hold the last frame for `PlaybackMode::Once`).
     next_state: Option<StateID>,
+    /// The order in which the frames are played
+    mode: PlaybackMode,
+    /// The number of times the playback reaches its end before moving to `next_state`
+    loops: usize,
     /// The "phase shift" of the animation in ms
     phase: f64,
+    /// If `Some` the range `[min, max)` in ms that the phase shift is randomly picked from for
+    /// every instance, instead of `phase`
+    random_phase: Option<(f64, f64)>,
+    #[serde(skip_serializing)]
     frames_per_increment: f64,
     fluidity: f64,
+    /// The named points in the playback that are reported when crossed
+    markers: Vec<Marker>,
     #[serde(skip)]
     phantom: PhantomData<Sprite>,
 }
 
+#[derive(Deserialize)]
+/// The serialized settings of an `IndexState`
+struct IndexStateFields {
+    min_i: usize,
+    max_i: usize,
+    frame_rate: FrameRate,
+    #[serde(default)]
+    next_state: Option<StateID>,
+    #[serde(default)]
+    mode: PlaybackMode,
+    #[serde(default = "default_loops")]
+    loops: usize,
+    #[serde(default)]
+    phase: f64,
+    #[serde(default)]
+    random_phase: Option<(f64, f64)>,
+    #[serde(default = "default_fluidity")]
+    fluidity: f64,
+    #[serde(default)]
+    markers: Vec<Marker>,
+}
+
+impl<S> From<IndexStateFields> for IndexState<S> {
+    fn from(fields: IndexStateFields) -> Self {
+        let mut state = Self {
+            min_i: fields.min_i,
+            max_i: fields.max_i,
+            frame_rate: fields.frame_rate,
+            nominal_mspf: 0.,
+            actual_mspf: 0.,
+            next_state: fields.next_state,
+            mode: fields.mode,
+            loops: fields.loops,
+            phase: fields.phase,
+            random_phase: fields.random_phase,
+            frames_per_increment: 0.,
+            fluidity: fields.fluidity,
+            markers: fields.markers,
+            phantom: PhantomData,
+        };
+        // Bad values are left for `validate` to report along with the rest of the machine
+        state.derive_timing();
+        state
+    }
+}
+
 impl<S> IndexState<S> {
     /// Make a new index state
     /// * `min_i` The minimum index in the sprite sheet that this state should use (inclusive).
     /// * `max_i` The maximum index in the sprite sheet that this state should use (inclusive).
     /// * `mspf` The "average" frame rate of the animation.
-    ///         When `fluidity_factor` is 1, this is the number of milliseconds that a single frame is rendered
-    /// * `next_state` If `Some` the state to switch to after reaching `max_i`, otherwise loop on this state.
+    ///   When `fluidity_factor` is 1, this is the number of milliseconds that a single frame is rendered
+    /// * `next_state` If `Some` the state to switch to after reaching the end of the playback, otherwise loop on this state.
     /// * `phase` If `Some` the phase shift of the animation in frames (default 0).
     /// * `fluidity_factor` From (0, 1] the fluidity of the animation as a whole (default 1).
+    ///
+    /// A `nominal_mspf` that isn't positive and finite is reported by `AnimationState::validate`, and
+    /// the playback doesn't move until it is fixed.
+    ///
+    /// # Panics
+    /// If `fluidity_factor` is outside of (0, 1]. `IndexState::builder` reports this as an error instead.
     pub fn new(
         min_i: usize,
         max_i: usize,
@@ -45,58 +163,325 @@ impl<S> IndexState<S> {
         phase: Option<f64>,
         fluidity_factor: Option<f64>,
     ) -> Self {
-        let fluidity_factor = fluidity_factor.unwrap_or_else(|| 1.);
+        let fluidity_factor = fluidity_factor.unwrap_or(1.);
         if fluidity_factor > 1. || fluidity_factor <= 0. {
             panic!("Animation fluidity factor must be in the range (0, 1]");
         }
 
-        let actual_mspf = nominal_mspf / fluidity_factor;
-        let frames_per_increment = 1. / fluidity_factor;
-
-        Self {
+        let mut state = Self {
             min_i,
             max_i,
-            nominal_mspf,
-            actual_mspf,
+            frame_rate: FrameRate::Mspf(nominal_mspf),
+            nominal_mspf: 0.,
+            actual_mspf: 0.,
             next_state,
+            mode: PlaybackMode::default(),
+            loops: 1,
             phase: phase.unwrap_or_default(),
-            frames_per_increment,
+            random_phase: None,
+            frames_per_increment: 0.,
             fluidity: fluidity_factor,
-            phantom: PhantomData::default(),
-        }
+            markers: Vec::new(),
+            phantom: PhantomData,
+        };
+        state.derive_timing();
+        state
     }
 
-    fn maybe_increment(&self, data: &mut IndexData<S>) {
-        let mut effective_time_elapsed = data.ms_elapsed;
+    /// Recomputes the frame durations from the frame rate, fluidity, mode, loops and next state
+    fn derive_timing(&mut self) {
+        self.nominal_mspf = self.frame_rate.mspf(self.span_positions());
+        self.actual_mspf = self.nominal_mspf / self.fluidity;
+        self.frames_per_increment = 1. / self.fluidity;
+    }
 
+    /// Whether the frame durations derived from the frame rate are positive and finite
+    fn has_valid_timing(&self) -> bool {
+        self.nominal_mspf.is_finite() && self.nominal_mspf > 0.
+    }
+
+    /// Starts building an index state, see `IndexStateBuilder`
+    pub fn builder() -> IndexStateBuilder<S> {
+        IndexStateBuilder::default()
+    }
+
+    fn maybe_increment(&self, data: &mut IndexData<S>, delta_ms: f64, markers: &mut Vec<String>) {
+        if !(self.actual_mspf.is_finite() && self.actual_mspf > 0.) {
+            // A frame rate or fluidity that `validate` reports, the playback can't move
+            return;
+        }
+        let mut delta_ms = delta_ms;
         if data.phase_delay > 0. {
-            if !(data.ms_elapsed > data.phase_delay) {
+            data.ms_elapsed += delta_ms;
+            if data.ms_elapsed <= data.phase_delay {
                 return;
             }
-            effective_time_elapsed -= data.phase_delay;
-            // Don't delay future frames in this state
+            // Only the time past the delay is played, and future frames in this state aren't delayed
+            delta_ms = data.ms_elapsed - data.phase_delay;
             data.phase_delay = 0.;
         }
 
-        let nominal_num_frames = f64::floor(effective_time_elapsed / self.nominal_mspf);
+        // Everything is derived from the playback time alone, so one large delta ends up in exactly
+        // the same place as many small ones
+        let from_ms = data.playback_ms;
+        let mut to_ms = from_ms + delta_ms;
+        let mut finished = false;
+        if let Some(finish_ms) = self.finish_ms() {
+            // Stop where the state moves on, the rest of the time is played by `next_state`
+            if to_ms >= finish_ms {
+                data.overflow_ms = to_ms - finish_ms;
+                to_ms = finish_ms;
+                finished = true;
+            }
+        }
+        let from_start = data.loops_completed == 0;
+        cross_time(&self.markers, from_ms, to_ms, self.loop_ms(), markers);
+        data.playback_ms = to_ms;
+
+        let end_ms = self.last_position() as f64 * self.nominal_mspf;
         // If we checked for reaching the end based on the actual frame it would lead to completly fluid
         // animations reaching the end some number of frames early.
         // Instead we check if the animation is at the end based on if it were running completely fluidly
-        let nominal_next_index = data.index + nominal_num_frames as usize;
-        if nominal_next_index >= self.max_i {
+        if self.mode == PlaybackMode::Once {
+            if to_ms >= end_ms {
+                data.reached_end = true;
+                if from_start && self.next_state.is_none() {
+                    data.overflow_ms = to_ms - end_ms;
+                }
+                data.loops_completed = 1;
+            }
+        } else {
+            let loops = self.loops_crossed(from_ms, to_ms, from_start);
+            if loops > 0 {
+                if self.next_state.is_none() {
+                    // Measured from the first end reached, which is when a queued state takes over
+                    data.overflow_ms =
+                        to_ms - end_ms - data.loops_completed as f64 * self.duration_ms();
+                }
+                data.loops_completed = data.loops_completed.saturating_add(loops);
+                data.reached_end = true;
+            }
+        }
+        if finished {
+            // The frame durations of a `FrameRate::Duration` may be rounded past `finish_ms`
             data.reached_end = true;
+            data.loops_completed = data.loops_completed.max(self.loops);
+        }
+
+        let from_position = self.unwrapped_position(from_ms);
+        let to_position = self.unwrapped_position(to_ms);
+        if !self.markers.is_empty() {
+            let mut last_crossed = to_position;
+            if self.mode == PlaybackMode::Once {
+                last_crossed = last_crossed.min(self.last_position());
+            }
+            for position in from_position..last_crossed {
+                cross_frame(
+                    &self.markers,
+                    self.index_at(self.wrap_position(position + 1)),
+                    markers,
+                );
+            }
         }
 
-        let num_frames = ((nominal_num_frames / self.frames_per_increment).floor()
-            * self.frames_per_increment) as usize;
-        data.ms_elapsed %= self.actual_mspf;
+        data.ms_elapsed = to_ms % self.actual_mspf;
+        data.position = self.wrap_position(to_position);
+        data.index = self.index_at(data.position);
+    }
 
-        let next_index = data.index + num_frames;
-        data.index = if next_index > self.max_i {
-            next_index - self.max_i + self.min_i - 1
-        } else {
-            next_index
+    /// The playback time at which the state moves on to `next_state`, `None` if it never does
+    fn finish_ms(&self) -> Option<f64> {
+        self.next_state.as_ref().map(|_| self.span_ms())
+    }
+
+    /// The number of frames into the playback after `playback_ms`, before wrapping around.
+    /// Frames only change every `actual_mspf`, in steps of `frames_per_increment`.
+    fn unwrapped_position(&self, playback_ms: f64) -> usize {
+        ((playback_ms / self.actual_mspf).floor() * self.frames_per_increment) as usize
+    }
+
+    /// The number of milliseconds in a loop of the playback, `None` if it doesn't loop
+    fn loop_ms(&self) -> Option<f64> {
+        match self.mode {
+            PlaybackMode::Once => None,
+            _ => Some(self.duration_ms()),
+        }
+    }
+
+    /// Whether the playback loops without ever moving on to `next_state`
+    fn loops_forever(&self) -> bool {
+        self.mode != PlaybackMode::Once && self.next_state.is_none()
+    }
+
+    /// The playback time that `normalized_time` runs over: up to where the playback ends, or a single
+    /// loop if it loops forever
+    fn span_ms(&self) -> f64 {
+        match (self.frame_rate, self.span_positions()) {
+            // Exactly the duration, rather than a rounded multiple of the frame durations
+            (FrameRate::Duration(duration_ms), positions) if positions > 0 => duration_ms,
+            (_, positions) => positions as f64 * self.nominal_mspf,
+        }
+    }
+
+    /// The number of frames shown over `span_ms`, which a `FrameRate::Duration` is spread over
+    fn span_positions(&self) -> usize {
+        match (self.mode, &self.next_state) {
+            (PlaybackMode::Once, _) => self.last_position(),
+            (_, Some(_)) => {
+                self.last_position() + self.loops.saturating_sub(1) * self.loop_positions()
+            }
+            (_, None) => self.loop_positions(),
+        }
+    }
+
+    /// The number of milliseconds it takes to play every position once
+    fn duration_ms(&self) -> f64 {
+        self.loop_positions() as f64 * self.nominal_mspf
+    }
+
+    /// The number of frames shown in a loop of the playback
+    fn loop_positions(&self) -> usize {
+        match self.mode {
+            PlaybackMode::PingPong => self.last_position().max(1),
+            _ => self.frame_count(),
+        }
+    }
+
+    /// The number of times a looping playback reaches its last position between `from_ms` (exclusive,
+    /// unless `from_start`) and `to_ms` (inclusive)
+    fn loops_crossed(&self, from_ms: f64, to_ms: f64, from_start: bool) -> usize {
+        let Some(loop_ms) = self.loop_ms().filter(|loop_ms| *loop_ms > 0.) else {
+            return 0;
         };
+        let last_ms = self.last_position() as f64 * self.nominal_mspf;
+        let reached_by = |ms: f64| {
+            if ms < last_ms {
+                0
+            } else {
+                // Saturates rather than overflowing for playback times far beyond any real playback
+                (((ms - last_ms) / loop_ms).floor() as usize).saturating_add(1)
+            }
+        };
+        if from_start && from_ms <= 0. {
+            // A single frame playback starts at its end
+            reached_by(to_ms)
+        } else {
+            reached_by(to_ms).saturating_sub(reached_by(from_ms))
+        }
+    }
+
+    /// Moves the playback to `playback_ms` after the phase delay, wrapping around or holding the last
+    /// frame depending on the mode
+    fn seek_playback(&self, data: &mut IndexData<S>, playback_ms: f64) {
+        data.playback_ms = playback_ms;
+        data.phase_delay = 0.;
+        data.reached_end = false;
+        data.loops_completed = self.loops_crossed(0., playback_ms, true);
+        if self.mode == PlaybackMode::Once
+            && playback_ms >= self.last_position() as f64 * self.nominal_mspf
+        {
+            data.loops_completed = 1;
+        }
+        data.overflow_ms = 0.;
+        data.ms_elapsed = playback_ms % self.actual_mspf;
+        data.position = self.wrap_position(self.unwrapped_position(playback_ms));
+        data.index = self.index_at(data.position);
+    }
+
+    /// The number of distinct frames between `min_i` and `max_i`
+    fn frame_count(&self) -> usize {
+        self.max_i.saturating_sub(self.min_i) + 1
+    }
+
+    /// The position at which the playback has reached its end
+    fn last_position(&self) -> usize {
+        match self.mode {
+            PlaybackMode::PingPong => 2 * (self.frame_count() - 1),
+            _ => self.frame_count() - 1,
+        }
+    }
+
+    /// Wraps a position that may have run past the end of the playback back into it
+    fn wrap_position(&self, position: usize) -> usize {
+        match self.mode {
+            PlaybackMode::Once => position.min(self.last_position()),
+            PlaybackMode::PingPong => position % self.last_position().max(1),
+            PlaybackMode::Forward | PlaybackMode::Reverse => position % self.frame_count(),
+        }
+    }
+
+    /// The sprite index shown at `position` frames into the playback
+    fn index_at(&self, position: usize) -> usize {
+        match self.mode {
+            PlaybackMode::Forward | PlaybackMode::Once => self.min_i + position,
+            PlaybackMode::Reverse => self.max_i - position,
+            PlaybackMode::PingPong => {
+                let turn = self.frame_count() - 1;
+                if position <= turn {
+                    self.min_i + position
+                } else {
+                    self.max_i - (position - turn)
+                }
+            }
+        }
+    }
+
+    /// Sets the order in which the frames are played
+    pub fn with_mode(mut self, mode: PlaybackMode) -> Self {
+        self.mode = mode;
+        // A duration is spread over a different number of frames depending on the mode
+        self.derive_timing();
+        self
+    }
+
+    /// Sets how fast the frames are played, e.g. `FrameRate::Duration(800.)` to move on to the next
+    /// state after 800ms
+    pub fn with_frame_rate(mut self, frame_rate: FrameRate) -> Self {
+        self.frame_rate = frame_rate;
+        self.derive_timing();
+        self
+    }
+
+    /// How fast the frames are played
+    pub fn frame_rate(&self) -> FrameRate {
+        self.frame_rate
+    }
+
+    /// The order in which the frames are played
+    pub fn mode(&self) -> PlaybackMode {
+        self.mode
+    }
+
+    /// Sets the number of times the playback reaches its end before moving to `next_state` (default 1),
+    /// e.g. 3 to spin three times before stopping
+    pub fn with_loops(mut self, loops: usize) -> Self {
+        self.loops = loops;
+        // A duration is spread over every loop
+        self.derive_timing();
+        self
+    }
+
+    /// The number of times the playback reaches its end before moving to `next_state`
+    pub fn loops(&self) -> usize {
+        self.loops
+    }
+
+    /// Picks the phase shift of every instance randomly from `[min_ms, max_ms)`, instead of using
+    /// the same phase for all of them
+    pub fn with_random_phase(mut self, min_ms: f64, max_ms: f64) -> Self {
+        self.random_phase = Some((min_ms, max_ms));
+        self
+    }
+
+    /// Sets the named points in the playback that are reported when crossed
+    pub fn with_markers(mut self, markers: Vec<Marker>) -> Self {
+        self.markers = markers;
+        self
+    }
+
+    /// The named points in the playback that are reported when crossed
+    pub fn markers(&self) -> &[Marker] {
+        &self.markers
     }
 }
 
@@ -108,29 +493,310 @@ where
     type Data = IndexData<S>;
 
     fn start(&self) -> Self::Data {
-        IndexData::new(&self)
+        IndexData::new(self)
+    }
+
+    fn start_with_rng(&self, rng: &mut SeededRng) -> Self::Data {
+        let mut data = IndexData::new(self);
+        if let Some((min_ms, max_ms)) = self.random_phase {
+            data.phase_delay = rng.range_f64(min_ms, max_ms);
+            data.phase_ms = data.phase_delay;
+        }
+        data
     }
 
     fn update(
         &self,
         data: &mut Self::Data,
-        args: crate::state_machine::UpdateArgs,
+        args: UpdateArgs,
         sprite: &mut Self::Sprite,
+        markers: &mut Vec<String>,
     ) {
-        data.ms_elapsed += args.delta_ms;
-        self.maybe_increment(data);
+        if !data.started {
+            data.started = true;
+            cross_frame(&self.markers, data.index, markers);
+        }
+        data.reached_end = false;
+        data.overflow_ms = 0.;
+        self.maybe_increment(data, args.delta_ms, markers);
         sprite.set_index(data.index);
     }
 
-    fn next_state(&self, data: &Self::Data) -> Option<StateID> {
-        self.next_state.as_ref().and_then(|next| {
-            if data.index >= self.max_i || data.reached_end {
-                Some(next.clone())
-            } else {
-                None
-            }
+    fn next_state(&self, data: &Self::Data) -> Option<usize> {
+        // `next_state` is the only target
+        (self.next_state.is_some() && data.reached_end && data.loops_completed >= self.loops)
+            .then_some(0)
+    }
+
+    fn reached_end(&self, data: &Self::Data) -> bool {
+        data.reached_end
+    }
+
+    fn length_ms(&self) -> Option<f64> {
+        let span_ms = self.span_ms();
+        (span_ms > 0.).then_some(span_ms)
+    }
+
+    fn normalized_time(&self, data: &Self::Data) -> Option<f64> {
+        let span_ms = self.length_ms()?;
+        Some(if self.loops_forever() {
+            (data.playback_ms % span_ms) / span_ms
+        } else {
+            (data.playback_ms / span_ms).min(1.)
         })
     }
+
+    fn set_normalized_time(
+        &self,
+        data: &mut Self::Data,
+        normalized_time: f64,
+        sprite: &mut Self::Sprite,
+    ) {
+        let Some(span_ms) = self.length_ms() else {
+            return;
+        };
+        // Stay in the current loop so the playback keeps counting up
+        let loops = if self.loops_forever() {
+            (data.playback_ms / span_ms).floor()
+        } else {
+            0.
+        };
+        self.seek_playback(data, (loops + normalized_time.clamp(0., 1.)) * span_ms);
+        sprite.set_index(data.index);
+    }
+
+    fn overflow_ms(&self, data: &Self::Data) -> f64 {
+        data.overflow_ms
+    }
+
+    fn total_duration_ms(&self, data: &Self::Data) -> Option<f64> {
+        (!self.loops_forever()).then(|| data.phase_ms + self.span_ms())
+    }
+
+    fn elapsed_ms(&self, data: &Self::Data) -> Option<f64> {
+        if data.phase_delay > 0. {
+            // Still waiting for the phase delay, which `ms_elapsed` counts towards
+            Some(data.ms_elapsed)
+        } else {
+            Some(data.phase_ms + data.playback_ms)
+        }
+    }
+
+    fn remaining_ms(&self, data: &Self::Data) -> Option<f64> {
+        let elapsed_ms = self.elapsed_ms(data)?;
+        match (self.total_duration_ms(data), self.loop_ms()) {
+            (Some(total_ms), _) => Some((total_ms - elapsed_ms).max(0.)),
+            (None, Some(loop_ms)) if loop_ms > 0. => Some(loop_ms - data.playback_ms % loop_ms),
+            _ => None,
+        }
+    }
+
+    fn seek(&self, data: &mut Self::Data, ms: f64, sprite: &mut Self::Sprite) {
+        let ms = ms.max(0.);
+        if ms < data.phase_ms {
+            // Back before the playback started, the phase delay has yet to pass
+            self.seek_playback(data, 0.);
+            data.phase_delay = data.phase_ms;
+            data.ms_elapsed = ms;
+        } else {
+            self.seek_playback(data, ms - data.phase_ms);
+        }
+        sprite.set_index(data.index);
+    }
+
+    fn targets(&self) -> Vec<StateID> {
+        self.next_state.iter().cloned().collect()
+    }
+
+    fn validate(&self, id: &StateID, frame_count: Option<usize>) -> Vec<ValidationError> {
+        let mut problems = Vec::new();
+        if self.min_i > self.max_i {
+            problems.push(ValidationError::InvalidRange {
+                state: id.clone(),
+                min: self.min_i,
+                max: self.max_i,
+            });
+        }
+        if !self.frame_rate.is_valid() || !self.has_valid_timing() {
+            problems.push(ValidationError::InvalidFrameRate(id.clone()));
+        }
+        if self.fluidity > 1. || self.fluidity <= 0. {
+            problems.push(ValidationError::InvalidFluidity(id.clone()));
+        }
+        if !valid_phase(self.phase, self.random_phase) {
+            problems.push(ValidationError::InvalidPhase(id.clone()));
+        }
+        if let Some(frame_count) = frame_count {
+            let last = self.min_i.max(self.max_i);
+            if last >= frame_count {
+                problems.push(ValidationError::FrameOutOfBounds {
+                    state: id.clone(),
+                    index: last,
+                    frame_count,
+                });
+            }
+        }
+        problems
+    }
+}
+
+/// Whether a phase shift, and the range a random one is picked from, are positive or 0 and finite
+fn valid_phase(phase: f64, random_phase: Option<(f64, f64)>) -> bool {
+    let valid = |phase_ms: f64| phase_ms >= 0. && phase_ms.is_finite();
+    valid(phase)
+        && random_phase
+            .is_none_or(|(min_ms, max_ms)| valid(min_ms) && valid(max_ms) && min_ms <= max_ms)
+}
+
+#[derive(Debug, Clone)]
+/// Builds an `IndexState` step by step, reporting bad settings as errors instead of panicking.
+///
+/// Only the frames and the frame rate have to be set.
+pub struct IndexStateBuilder<Sprite> {
+    frames: Option<RangeInclusive<usize>>,
+    frame_rate: Option<FrameRate>,
+    next_state: Option<StateID>,
+    mode: PlaybackMode,
+    loops: usize,
+    phase: f64,
+    random_phase: Option<(f64, f64)>,
+    fluidity: f64,
+    markers: Vec<Marker>,
+    phantom: PhantomData<Sprite>,
+}
+
+impl<S> Default for IndexStateBuilder<S> {
+    fn default() -> Self {
+        Self {
+            frames: None,
+            frame_rate: None,
+            next_state: None,
+            mode: PlaybackMode::default(),
+            loops: 1,
+            phase: 0.,
+            random_phase: None,
+            fluidity: 1.,
+            markers: Vec::new(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<S> IndexStateBuilder<S> {
+    /// Sets the indices in the sprite sheet the state plays, e.g. `0..=9`
+    pub fn frames(mut self, frames: RangeInclusive<usize>) -> Self {
+        self.frames = Some(frames);
+        self
+    }
+
+    /// Sets the "average" number of frames shown per second
+    pub fn fps(mut self, fps: impl Into<f64>) -> Self {
+        self.frame_rate = Some(FrameRate::Fps(fps.into()));
+        self
+    }
+
+    /// Sets the "average" number of milliseconds each frame is shown for
+    pub fn mspf(mut self, mspf: impl Into<f64>) -> Self {
+        self.frame_rate = Some(FrameRate::Mspf(mspf.into()));
+        self
+    }
+
+    /// Sets the number of milliseconds the playback lasts, see `FrameRate::Duration`
+    pub fn duration(mut self, duration_ms: impl Into<f64>) -> Self {
+        self.frame_rate = Some(FrameRate::Duration(duration_ms.into()));
+        self
+    }
+
+    /// Sets the state to switch to after reaching the end of the playback, instead of looping
+    pub fn then(mut self, next_state: impl Into<StateID>) -> Self {
+        self.next_state = Some(next_state.into());
+        self
+    }
+
+    /// Sets the order in which the frames are played
+    pub fn mode(mut self, mode: PlaybackMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the number of times the playback reaches its end before moving on, see `IndexState::with_loops`
+    pub fn loops(mut self, loops: usize) -> Self {
+        self.loops = loops;
+        self
+    }
+
+    /// Sets the phase shift of the animation in ms
+    pub fn phase(mut self, phase_ms: impl Into<f64>) -> Self {
+        self.phase = phase_ms.into();
+        self
+    }
+
+    /// Picks the phase shift of every instance randomly from `[min_ms, max_ms)`, see
+    /// `IndexState::with_random_phase`
+    pub fn random_phase(mut self, min_ms: impl Into<f64>, max_ms: impl Into<f64>) -> Self {
+        self.random_phase = Some((min_ms.into(), max_ms.into()));
+        self
+    }
+
+    /// Sets the fluidity of the animation as a whole, from (0, 1]
+    pub fn fluidity(mut self, fluidity: impl Into<f64>) -> Self {
+        self.fluidity = fluidity.into();
+        self
+    }
+
+    /// Adds a named point in the playback that is reported when crossed
+    pub fn marker(mut self, marker: Marker) -> Self {
+        self.markers.push(marker);
+        self
+    }
+
+    /// Builds the state, which is called `id` in the errors
+    pub fn build(self, id: &StateID) -> Result<IndexState<S>, ValidationError> {
+        let frames = self
+            .frames
+            .ok_or_else(|| ValidationError::NoFrames(id.clone()))?;
+        let (min, max) = frames.into_inner();
+        if min > max {
+            return Err(ValidationError::InvalidRange {
+                state: id.clone(),
+                min,
+                max,
+            });
+        }
+        let frame_rate = self
+            .frame_rate
+            .filter(|frame_rate| frame_rate.is_valid())
+            .ok_or_else(|| ValidationError::InvalidFrameRate(id.clone()))?;
+        if self.fluidity > 1. || self.fluidity <= 0. {
+            return Err(ValidationError::InvalidFluidity(id.clone()));
+        }
+        if !valid_phase(self.phase, self.random_phase) {
+            return Err(ValidationError::InvalidPhase(id.clone()));
+        }
+
+        let mut state = IndexState {
+            min_i: min,
+            max_i: max,
+            frame_rate,
+            nominal_mspf: 0.,
+            actual_mspf: 0.,
+            next_state: self.next_state,
+            mode: self.mode,
+            loops: self.loops,
+            phase: self.phase,
+            random_phase: self.random_phase,
+            frames_per_increment: 0.,
+            fluidity: self.fluidity,
+            markers: self.markers,
+            phantom: PhantomData,
+        };
+        state.derive_timing();
+        // A valid frame rate can still give frame durations that overflow, i.e. `fps(1e-310)`
+        if !state.has_valid_timing() {
+            return Err(ValidationError::InvalidFrameRate(id.clone()));
+        }
+        Ok(state)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -139,11 +805,28 @@ where
 pub struct IndexData<Sprite> {
     /// The current index of the state
     pub index: usize,
+    /// The number of frames into the playback, which may differ from `index` depending on the `PlaybackMode`
+    #[serde(default)]
+    pub position: usize,
     /// The total number of milliseconds that have passed since the last frame update
     pub ms_elapsed: f64,
     /// The number of ms to "wait" before updating the state for the first time
     pub phase_delay: f64,
+    /// The phase delay this instance started with
+    #[serde(default)]
+    pub phase_ms: f64,
+    /// The total number of milliseconds the playback has run for, excluding the phase delay
+    #[serde(default)]
+    pub playback_ms: f64,
+    /// The number of times the playback reached its end since the state started
+    #[serde(default)]
+    pub loops_completed: usize,
     reached_end: bool,
+    /// The ms of the last update left over after the playback reached its end
+    #[serde(default)]
+    overflow_ms: f64,
+    #[serde(default)]
+    started: bool,
     #[serde(skip)]
     phantom: PhantomData<Sprite>,
 }
@@ -152,11 +835,59 @@ impl<S> IndexData<S> {
     /// Creates the data from a given state
     pub fn new(state: &IndexState<S>) -> Self {
         Self {
-            index: state.min_i,
+            index: state.index_at(0),
+            position: 0,
             ms_elapsed: 0.,
             phase_delay: state.phase,
+            phase_ms: state.phase,
+            playback_ms: 0.,
+            loops_completed: 0,
             reached_end: false,
-            phantom: PhantomData::default(),
+            overflow_ms: 0.,
+            started: false,
+            phantom: PhantomData,
         }
     }
 }
+
+// Not derived, which would require the sprite to be `Clone`
+impl<S> Clone for IndexData<S> {
+    fn clone(&self) -> Self {
+        Self {
+            index: self.index,
+            position: self.position,
+            ms_elapsed: self.ms_elapsed,
+            phase_delay: self.phase_delay,
+            phase_ms: self.phase_ms,
+            playback_ms: self.playback_ms,
+            loops_completed: self.loops_completed,
+            reached_end: self.reached_end,
+            overflow_ms: self.overflow_ms,
+            started: self.started,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<S> StableHash for IndexData<S> {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        self.index.stable_hash(checksum);
+        self.position.stable_hash(checksum);
+        self.ms_elapsed.stable_hash(checksum);
+        self.phase_delay.stable_hash(checksum);
+        self.phase_ms.stable_hash(checksum);
+        self.playback_ms.stable_hash(checksum);
+        self.loops_completed.stable_hash(checksum);
+        self.reached_end.stable_hash(checksum);
+        self.overflow_ms.stable_hash(checksum);
+        self.started.stable_hash(checksum);
+    }
+}
+
+fn default_loops() -> usize {
+    1
+}
+
+fn default_fluidity() -> f64 {
+    1.
+}
@@ -1,2 +1,9 @@
+/// Animation states that play an explicit list of frames with their own durations
+pub mod frame_list;
 /// Animation states that set the current frame using some index
 pub mod index;
+/// Animation states that move on to one of several states at random
+pub mod random_choice;
+mod standard;
+
+pub use standard::{StandardData, StandardState};
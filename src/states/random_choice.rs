@@ -0,0 +1,181 @@
+use core::marker::PhantomData;
+
+#[cfg(feature = "bevy")]
+use bevy::{prelude::Component, reflect::TypePath};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::ValidationError,
+    state_machine::{
+        AnimationState, Checksum, IndexSprite, SeededRng, Sprite, StableHash, StateID, UpdateArgs,
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// One of the states a `RandomChoiceState` can move to
+pub struct Choice {
+    /// The state or group to move to
+    pub id: StateID,
+    /// How likely the choice is relative to the others
+    pub weight: f64,
+}
+
+impl Choice {
+    /// Whether the weight is positive and finite, other choices are never picked
+    fn can_be_picked(&self) -> bool {
+        self.weight > 0. && self.weight.is_finite()
+    }
+}
+
+impl From<(StateID, f64)> for Choice {
+    fn from((id, weight): (StateID, f64)) -> Self {
+        Choice { id, weight }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(TypePath))]
+/// A state that immediately moves to one of several states, picked at random by weight.
+///
+/// Useful for idle variations, e.g. an idle that moves back to a choice between "idle", "idle_scratch"
+/// and "idle_yawn". The choice is made with the instance's generator, so seeded instances are reproducible.
+pub struct RandomChoiceState<Sprite> {
+    /// The states to choose from
+    choices: Vec<Choice>,
+    #[serde(skip)]
+    phantom: PhantomData<Sprite>,
+}
+
+impl<S> RandomChoiceState<S> {
+    /// Make a new random choice state
+    /// * `choices` The states to choose from, with how likely each is relative to the others.
+    pub fn new(choices: Vec<impl Into<Choice>>) -> Self {
+        Self {
+            choices: choices.into_iter().map(Into::into).collect(),
+            phantom: PhantomData,
+        }
+    }
+
+    /// The states to choose from
+    pub fn choices(&self) -> &[Choice] {
+        &self.choices
+    }
+
+    /// The state picked by an instance, see `RandomChoiceData::choice`
+    pub fn chosen(&self, data: &RandomChoiceData) -> Option<&StateID> {
+        Some(&self.choices.get(data.choice?)?.id)
+    }
+
+    /// Picks the position of one of the choices by weight, ignoring those that can never be picked
+    fn choose(&self, rng: &mut SeededRng) -> Option<usize> {
+        let total: f64 = self
+            .choices
+            .iter()
+            .filter(|choice| choice.can_be_picked())
+            .map(|choice| choice.weight)
+            .sum();
+        if total <= 0. {
+            return None;
+        }
+        let mut roll = rng.range_f64(0., total);
+        let mut picked = None;
+        for (position, choice) in self.choices.iter().enumerate() {
+            if !choice.can_be_picked() {
+                continue;
+            }
+            picked = Some(position);
+            if roll < choice.weight {
+                break;
+            }
+            roll -= choice.weight;
+        }
+        picked
+    }
+}
+
+impl<S> AnimationState for RandomChoiceState<S>
+where
+    S: Send + Sync + Sprite + IndexSprite,
+{
+    type Sprite = S;
+    type Data = RandomChoiceData;
+
+    fn start(&self) -> Self::Data {
+        self.start_with_rng(&mut SeededRng::unique())
+    }
+
+    fn start_with_rng(&self, rng: &mut SeededRng) -> Self::Data {
+        RandomChoiceData {
+            choice: self.choose(rng),
+            delta_ms: 0.,
+        }
+    }
+
+    fn update(
+        &self,
+        data: &mut Self::Data,
+        args: UpdateArgs,
+        _sprite: &mut Self::Sprite,
+        _markers: &mut Vec<String>,
+    ) {
+        data.delta_ms = args.delta_ms;
+    }
+
+    fn next_state(&self, data: &Self::Data) -> Option<usize> {
+        // The choices are the targets, in the same order
+        data.choice
+    }
+
+    fn reached_end(&self, _data: &Self::Data) -> bool {
+        true
+    }
+
+    fn overflow_ms(&self, data: &Self::Data) -> f64 {
+        // Picking a state takes no time, so all of it is passed on
+        data.delta_ms
+    }
+
+    fn targets(&self) -> Vec<StateID> {
+        self.choices
+            .iter()
+            .map(|choice| choice.id.clone())
+            .collect()
+    }
+
+    fn validate(&self, id: &StateID, _frame_count: Option<usize>) -> Vec<ValidationError> {
+        // A weight of 0 is allowed, it keeps a choice around that is never picked
+        let mut problems: Vec<_> = self
+            .choices
+            .iter()
+            .enumerate()
+            .filter(|(_, choice)| !(choice.weight >= 0. && choice.weight.is_finite()))
+            .map(|(position, _)| ValidationError::InvalidWeight {
+                state: id.clone(),
+                position,
+            })
+            .collect();
+        if !self.choices.iter().any(Choice::can_be_picked) {
+            problems.push(ValidationError::NoChoices(id.clone()));
+        }
+        problems
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(Component))]
+/// The per-instance data of a `RandomChoiceState`
+pub struct RandomChoiceData {
+    /// The position in `RandomChoiceState::choices` of the state picked when the instance entered the
+    /// state, see `RandomChoiceState::chosen`
+    pub choice: Option<usize>,
+    /// The ms passed to the last update
+    #[serde(default)]
+    delta_ms: f64,
+}
+
+impl StableHash for RandomChoiceData {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        self.choice.stable_hash(checksum);
+        self.delta_ms.stable_hash(checksum);
+    }
+}
@@ -0,0 +1,282 @@
+#[cfg(feature = "bevy")]
+use bevy::reflect::TypePath;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    frame_list::{FrameListData, FrameListState},
+    index::{IndexData, IndexState},
+    random_choice::{RandomChoiceData, RandomChoiceState},
+};
+use crate::{
+    error::ValidationError,
+    state_machine::{
+        AnimationState, Checksum, IndexSprite, SeededRng, Sprite, StableHash, StateID, UpdateArgs,
+    },
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "bevy", derive(TypePath))]
+#[serde(bound = "")]
+/// Any of the crate's built in states, allowing them to be mixed within one state machine
+pub enum StandardState<Sprite> {
+    /// See `IndexState`
+    Index(IndexState<Sprite>),
+    /// See `FrameListState`
+    FrameList(FrameListState<Sprite>),
+    /// See `RandomChoiceState`
+    RandomChoice(RandomChoiceState<Sprite>),
+}
+
+impl<S> From<IndexState<S>> for StandardState<S> {
+    fn from(value: IndexState<S>) -> Self {
+        StandardState::Index(value)
+    }
+}
+
+impl<S> From<FrameListState<S>> for StandardState<S> {
+    fn from(value: FrameListState<S>) -> Self {
+        StandardState::FrameList(value)
+    }
+}
+
+impl<S> From<RandomChoiceState<S>> for StandardState<S> {
+    fn from(value: RandomChoiceState<S>) -> Self {
+        StandardState::RandomChoice(value)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(bound = "")]
+/// The per-instance data of a `StandardState`
+pub enum StandardData<Sprite> {
+    /// See `IndexData`
+    Index(IndexData<Sprite>),
+    /// See `FrameListData`
+    FrameList(FrameListData<Sprite>),
+    /// See `RandomChoiceData`
+    RandomChoice(RandomChoiceData),
+}
+
+// Not derived, which would require the sprite to be `Clone`
+impl<S> Clone for StandardData<S> {
+    fn clone(&self) -> Self {
+        match self {
+            StandardData::Index(data) => StandardData::Index(data.clone()),
+            StandardData::FrameList(data) => StandardData::FrameList(data.clone()),
+            StandardData::RandomChoice(data) => StandardData::RandomChoice(data.clone()),
+        }
+    }
+}
+
+impl<S> StableHash for StandardData<S> {
+    fn stable_hash(&self, checksum: &mut Checksum) {
+        match self {
+            StandardData::Index(data) => {
+                0_u64.stable_hash(checksum);
+                data.stable_hash(checksum);
+            }
+            StandardData::FrameList(data) => {
+                1_u64.stable_hash(checksum);
+                data.stable_hash(checksum);
+            }
+            StandardData::RandomChoice(data) => {
+                2_u64.stable_hash(checksum);
+                data.stable_hash(checksum);
+            }
+        }
+    }
+}
+
+impl<S> AnimationState for StandardState<S>
+where
+    S: Send + Sync + Sprite + IndexSprite,
+{
+    type Sprite = S;
+    type Data = StandardData<S>;
+
+    fn start(&self) -> Self::Data {
+        match self {
+            StandardState::Index(state) => StandardData::Index(state.start()),
+            StandardState::FrameList(state) => StandardData::FrameList(state.start()),
+            StandardState::RandomChoice(state) => StandardData::RandomChoice(state.start()),
+        }
+    }
+
+    fn start_with_rng(&self, rng: &mut SeededRng) -> Self::Data {
+        match self {
+            StandardState::Index(state) => StandardData::Index(state.start_with_rng(rng)),
+            StandardState::FrameList(state) => StandardData::FrameList(state.start_with_rng(rng)),
+            StandardState::RandomChoice(state) => {
+                StandardData::RandomChoice(state.start_with_rng(rng))
+            }
+        }
+    }
+
+    fn update(
+        &self,
+        data: &mut Self::Data,
+        args: UpdateArgs,
+        sprite: &mut Self::Sprite,
+        markers: &mut Vec<String>,
+    ) {
+        match (self, data) {
+            (StandardState::Index(state), StandardData::Index(data)) => {
+                state.update(data, args, sprite, markers)
+            }
+            (StandardState::FrameList(state), StandardData::FrameList(data)) => {
+                state.update(data, args, sprite, markers)
+            }
+            (StandardState::RandomChoice(state), StandardData::RandomChoice(data)) => {
+                state.update(data, args, sprite, markers)
+            }
+            // The data was not created by this state, restart it
+            (state, data) => *data = state.start(),
+        }
+    }
+
+    fn next_state(&self, data: &Self::Data) -> Option<usize> {
+        match (self, data) {
+            (StandardState::Index(state), StandardData::Index(data)) => state.next_state(data),
+            (StandardState::FrameList(state), StandardData::FrameList(data)) => {
+                state.next_state(data)
+            }
+            (StandardState::RandomChoice(state), StandardData::RandomChoice(data)) => {
+                state.next_state(data)
+            }
+            _ => None,
+        }
+    }
+
+    fn reached_end(&self, data: &Self::Data) -> bool {
+        match (self, data) {
+            (StandardState::Index(state), StandardData::Index(data)) => state.reached_end(data),
+            (StandardState::FrameList(state), StandardData::FrameList(data)) => {
+                state.reached_end(data)
+            }
+            (StandardState::RandomChoice(state), StandardData::RandomChoice(data)) => {
+                state.reached_end(data)
+            }
+            _ => false,
+        }
+    }
+
+    fn overflow_ms(&self, data: &Self::Data) -> f64 {
+        match (self, data) {
+            (StandardState::Index(state), StandardData::Index(data)) => state.overflow_ms(data),
+            (StandardState::FrameList(state), StandardData::FrameList(data)) => {
+                state.overflow_ms(data)
+            }
+            (StandardState::RandomChoice(state), StandardData::RandomChoice(data)) => {
+                state.overflow_ms(data)
+            }
+            _ => 0.,
+        }
+    }
+
+    fn length_ms(&self) -> Option<f64> {
+        match self {
+            StandardState::Index(state) => state.length_ms(),
+            StandardState::FrameList(state) => state.length_ms(),
+            StandardState::RandomChoice(state) => state.length_ms(),
+        }
+    }
+
+    fn normalized_time(&self, data: &Self::Data) -> Option<f64> {
+        match (self, data) {
+            (StandardState::Index(state), StandardData::Index(data)) => state.normalized_time(data),
+            (StandardState::FrameList(state), StandardData::FrameList(data)) => {
+                state.normalized_time(data)
+            }
+            _ => None,
+        }
+    }
+
+    fn set_normalized_time(
+        &self,
+        data: &mut Self::Data,
+        normalized_time: f64,
+        sprite: &mut Self::Sprite,
+    ) {
+        match (self, data) {
+            (StandardState::Index(state), StandardData::Index(data)) => {
+                state.set_normalized_time(data, normalized_time, sprite)
+            }
+            (StandardState::FrameList(state), StandardData::FrameList(data)) => {
+                state.set_normalized_time(data, normalized_time, sprite)
+            }
+            _ => {}
+        }
+    }
+
+    fn total_duration_ms(&self, data: &Self::Data) -> Option<f64> {
+        match (self, data) {
+            (StandardState::Index(state), StandardData::Index(data)) => {
+                state.total_duration_ms(data)
+            }
+            (StandardState::FrameList(state), StandardData::FrameList(data)) => {
+                state.total_duration_ms(data)
+            }
+            (StandardState::RandomChoice(state), StandardData::RandomChoice(data)) => {
+                state.total_duration_ms(data)
+            }
+            _ => None,
+        }
+    }
+
+    fn elapsed_ms(&self, data: &Self::Data) -> Option<f64> {
+        match (self, data) {
+            (StandardState::Index(state), StandardData::Index(data)) => state.elapsed_ms(data),
+            (StandardState::FrameList(state), StandardData::FrameList(data)) => {
+                state.elapsed_ms(data)
+            }
+            (StandardState::RandomChoice(state), StandardData::RandomChoice(data)) => {
+                state.elapsed_ms(data)
+            }
+            _ => None,
+        }
+    }
+
+    fn remaining_ms(&self, data: &Self::Data) -> Option<f64> {
+        match (self, data) {
+            (StandardState::Index(state), StandardData::Index(data)) => state.remaining_ms(data),
+            (StandardState::FrameList(state), StandardData::FrameList(data)) => {
+                state.remaining_ms(data)
+            }
+            (StandardState::RandomChoice(state), StandardData::RandomChoice(data)) => {
+                state.remaining_ms(data)
+            }
+            _ => None,
+        }
+    }
+
+    fn seek(&self, data: &mut Self::Data, ms: f64, sprite: &mut Self::Sprite) {
+        match (self, data) {
+            (StandardState::Index(state), StandardData::Index(data)) => {
+                state.seek(data, ms, sprite)
+            }
+            (StandardState::FrameList(state), StandardData::FrameList(data)) => {
+                state.seek(data, ms, sprite)
+            }
+            (StandardState::RandomChoice(state), StandardData::RandomChoice(data)) => {
+                state.seek(data, ms, sprite)
+            }
+            _ => {}
+        }
+    }
+
+    fn targets(&self) -> Vec<StateID> {
+        match self {
+            StandardState::Index(state) => state.targets(),
+            StandardState::FrameList(state) => state.targets(),
+            StandardState::RandomChoice(state) => state.targets(),
+        }
+    }
+
+    fn validate(&self, id: &StateID, frame_count: Option<usize>) -> Vec<ValidationError> {
+        match self {
+            StandardState::Index(state) => state.validate(id, frame_count),
+            StandardState::FrameList(state) => state.validate(id, frame_count),
+            StandardState::RandomChoice(state) => state.validate(id, frame_count),
+        }
+    }
+}
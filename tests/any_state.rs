@@ -0,0 +1,161 @@
+mod common;
+
+use common::{args, id, Machine, TestSprite};
+use dynastes::state_machine::{
+    AlreadyPlaying, AnyStateTransition, Condition, Transition, TransitionReason, UpdateReport,
+};
+
+fn trigger(name: &str) -> Condition {
+    Condition::Trigger(name.to_string())
+}
+
+fn machine() -> Machine {
+    Machine::builder()
+        .state("idle", |s| s.frames(0..=3).mspf(100))
+        .state("walk", |s| s.frames(10..=13).mspf(100))
+        .state("hurt", |s| s.frames(20..=22).mspf(100).then("idle"))
+        .state("dead", |s| s.frames(30..=32).mspf(100))
+        .state("stunned", |s| s.frames(40..=41).mspf(100))
+        .group("loco", "idle", ["idle", "walk"])
+        .transition(Transition::new(id("idle"), id("walk")).when(trigger("hurt")))
+        .any_state_transition(AnyStateTransition::new(id("hurt")).when(trigger("hurt")))
+        .any_state_transition(
+            AnyStateTransition::new(id("dead"))
+                .when(trigger("die"))
+                .with_priority(10),
+        )
+        .any_state_transition(
+            AnyStateTransition::new(id("stunned"))
+                .when(trigger("hurt"))
+                .except(id("loco")),
+        )
+        .build()
+        .unwrap()
+}
+
+fn entered<'a>(machine: &'a Machine, report: &UpdateReport) -> Vec<&'a str> {
+    report
+        .transitions
+        .iter()
+        .map(|transition| machine.id_at(transition.to).unwrap().0.as_str())
+        .collect()
+}
+
+#[test]
+fn any_state_transitions_come_before_regular_ones() {
+    let machine = machine();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    instance.parameters.set_trigger("hurt");
+    let report = machine.update(&mut instance, args(10.), &mut sprite);
+    assert_eq!(entered(&machine, &report), ["hurt"]);
+    assert_eq!(report.transitions[0].reason, TransitionReason::AnyState);
+}
+
+#[test]
+fn higher_priorities_are_checked_first() {
+    let machine = machine();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    // "dead" was added after "hurt" but has a higher priority
+    instance.parameters.set_trigger("hurt");
+    instance.parameters.set_trigger("die");
+    let report = machine.update(&mut instance, args(10.), &mut sprite);
+    assert_eq!(entered(&machine, &report), ["dead"]);
+    // The trigger of the transition not taken is kept
+    assert!(instance.parameters.is_triggered("hurt"));
+}
+
+#[test]
+fn equal_priorities_are_checked_in_the_order_they_were_added() {
+    let machine = machine();
+    let mut instance = machine.try_new_instance(id("dead")).unwrap();
+    let mut sprite = TestSprite::default();
+
+    // Both "hurt" and "stunned" can be left from "dead", "hurt" was added first
+    instance.parameters.set_trigger("hurt");
+    let report = machine.update(&mut instance, args(10.), &mut sprite);
+    assert_eq!(entered(&machine, &report), ["hurt"]);
+}
+
+#[test]
+fn excluded_states_and_groups_are_never_left() {
+    let machine = Machine::builder()
+        .state("idle", |s| s.frames(0..=3).mspf(100))
+        .state("walk", |s| s.frames(10..=13).mspf(100))
+        .state("swim", |s| s.frames(20..=23).mspf(100))
+        .state("hurt", |s| s.frames(30..=32).mspf(100).then("idle"))
+        .group("loco", "idle", ["idle", "walk"])
+        .any_state_transition(
+            AnyStateTransition::new(id("hurt"))
+                .when(trigger("hurt"))
+                .except(id("loco")),
+        )
+        .build()
+        .unwrap();
+    let mut sprite = TestSprite::default();
+
+    // The members of "loco" are excluded through their group
+    for (state, expected) in [("idle", "idle"), ("walk", "walk"), ("swim", "hurt")] {
+        let mut instance = machine.try_new_instance(id(state)).unwrap();
+        instance.parameters.set_trigger("hurt");
+        machine.update(&mut instance, args(10.), &mut sprite);
+        assert_eq!(instance.current_id, id(expected), "{state}");
+    }
+
+    let machine = Machine::builder()
+        .state("idle", |s| s.frames(0..=3).mspf(100))
+        .state("walk", |s| s.frames(10..=13).mspf(100))
+        .state("hurt", |s| s.frames(30..=32).mspf(100).then("idle"))
+        .any_state_transition(
+            AnyStateTransition::new(id("hurt"))
+                .when(trigger("hurt"))
+                .except(id("walk")),
+        )
+        .build()
+        .unwrap();
+    for (state, expected) in [("idle", "hurt"), ("walk", "walk")] {
+        let mut instance = machine.try_new_instance(id(state)).unwrap();
+        instance.parameters.set_trigger("hurt");
+        machine.update(&mut instance, args(10.), &mut sprite);
+        assert_eq!(instance.current_id, id(expected), "{state}");
+    }
+}
+
+#[test]
+fn any_state_transitions_do_not_re_enter_their_target() {
+    let machine = Machine::builder()
+        .state("idle", |s| s.frames(0..=3).mspf(100))
+        .state("walk", |s| s.frames(10..=13).mspf(100))
+        .state("hurt", |s| s.frames(20..=22).mspf(100))
+        .group("loco", "idle", ["idle", "walk"])
+        .default_state("hurt")
+        .any_state_transition(AnyStateTransition::new(id("hurt")).when(trigger("hurt")))
+        .any_state_transition(AnyStateTransition::new(id("loco")).when(trigger("recover")))
+        .build()
+        .unwrap();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    // Getting hurt again doesn't restart "hurt", and the trigger is left for later
+    instance.parameters.set_trigger("hurt");
+    let report = machine.update(&mut instance, args(250.), &mut sprite);
+    assert!(report.transitions.is_empty());
+    assert_eq!(sprite.0, 22);
+    assert!(instance.parameters.is_triggered("hurt"));
+    instance.parameters.reset_trigger("hurt");
+
+    // Neither is a group re-entered from any of its members
+    instance.parameters.set_trigger("recover");
+    let report = machine.update(&mut instance, args(10.), &mut sprite);
+    assert_eq!(entered(&machine, &report), ["idle"]);
+    machine
+        .play(&mut instance, id("walk"), AlreadyPlaying::Restart)
+        .unwrap();
+    instance.parameters.set_trigger("recover");
+    let report = machine.update(&mut instance, args(10.), &mut sprite);
+    assert!(report.transitions.is_empty());
+    assert_eq!(instance.current_id, id("walk"));
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use common::{args, id, TestSprite};
+use dynastes::{
+    aseprite::{AsepriteSheet, UNTAGGED_STATE_ID},
+    state_machine::AnimationStateMachine,
+    states::{frame_list::Frame, StandardState},
+};
+
+fn frame(x: u32, duration: u32) -> String {
+    format!(
+        r#"{{"frame":{{"x":{x},"y":0,"w":16,"h":16}},"rotated":false,"trimmed":false,"duration":{duration}}}"#
+    )
+}
+
+fn sheet(frames: &str, tags: &str) -> String {
+    format!(
+        r#"{{"frames":{frames},"meta":{{"app":"http://www.aseprite.org/","image":"sheet.png","format":"RGBA8888","size":{{"w":64,"h":16}},"scale":"1","frameTags":[{tags}]}}}}"#
+    )
+}
+
+fn array_sheet(tags: &str) -> AsepriteSheet {
+    let frames = [frame(0, 100), frame(16, 50), frame(32, 75), frame(48, 20)].join(",");
+    AsepriteSheet::from_json(&sheet(&format!("[{frames}]"), tags)).unwrap()
+}
+
+fn frames(sheet: &AsepriteSheet, tag: &str) -> Vec<(usize, f64)> {
+    let tag = sheet
+        .meta
+        .frame_tags
+        .iter()
+        .find(|t| t.name == tag)
+        .unwrap();
+    sheet
+        .tag_frames(tag)
+        .unwrap()
+        .into_iter()
+        .map(|Frame { index, duration_ms }| (index, duration_ms))
+        .collect()
+}
+
+#[test]
+fn array_and_hash_layouts_are_read_in_order() {
+    let array = array_sheet("");
+    let hash = AsepriteSheet::from_json(&sheet(
+        &format!(
+            r#"{{"a 0.aseprite":{},"a 1.aseprite":{},"a 2.aseprite":{},"a 3.aseprite":{}}}"#,
+            frame(0, 100),
+            frame(16, 50),
+            frame(32, 75),
+            frame(48, 20)
+        ),
+        "",
+    ))
+    .unwrap();
+
+    for sheet in [array, hash] {
+        let xs: Vec<_> = sheet.frames.iter().map(|frame| frame.frame.x).collect();
+        assert_eq!(xs, [0, 16, 32, 48]);
+        assert_eq!(sheet.frames[1].duration, 50.);
+    }
+}
+
+#[test]
+fn tags_play_in_their_direction() {
+    let sheet = array_sheet(
+        r#"{"name":"fwd","from":0,"to":2,"direction":"forward"},
+        {"name":"rev","from":0,"to":2,"direction":"reverse"},
+        {"name":"pp","from":0,"to":3,"direction":"pingpong"},
+        {"name":"ppr","from":0,"to":3,"direction":"pingpong_reverse"}"#,
+    );
+    assert_eq!(frames(&sheet, "fwd"), [(0, 100.), (1, 50.), (2, 75.)]);
+    assert_eq!(frames(&sheet, "rev"), [(2, 75.), (1, 50.), (0, 100.)]);
+    let pp: Vec<_> = frames(&sheet, "pp").into_iter().map(|f| f.0).collect();
+    assert_eq!(pp, [0, 1, 2, 3, 2, 1]);
+    let ppr: Vec<_> = frames(&sheet, "ppr").into_iter().map(|f| f.0).collect();
+    assert_eq!(ppr, [3, 2, 1, 0, 1, 2]);
+}
+
+#[test]
+fn tags_outside_the_sheet_are_rejected() {
+    let sheet = array_sheet(r#"{"name":"bad","from":2,"to":9}"#);
+    assert!(sheet.states::<TestSprite>().is_err());
+}
+
+#[test]
+fn every_tag_becomes_a_state_with_the_first_as_default() {
+    let sheet = array_sheet(r#"{"name":"walk","from":0,"to":1},{"name":"blink","from":2,"to":3}"#);
+    let machine: AnimationStateMachine<TestSprite, StandardState<TestSprite>, ()> =
+        sheet.state_machine(()).unwrap();
+    assert_eq!(machine.default_id(), &id("walk"));
+    assert_eq!(machine.states().len(), 2);
+
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+    let mut indices = Vec::new();
+    for _ in 0..4 {
+        machine.update(&mut instance, args(50.), &mut sprite);
+        indices.push(sprite.0);
+    }
+    // Frame 0 is shown for 100ms and frame 1 for 50ms
+    assert_eq!(indices, [0, 1, 0, 0]);
+}
+
+#[test]
+fn untagged_sheets_play_every_frame() {
+    let sheet = array_sheet("");
+    assert_eq!(sheet.default_id(), id(UNTAGGED_STATE_ID));
+    let states = sheet.states::<TestSprite>().unwrap();
+    assert_eq!(states.len(), 1);
+    assert_eq!(states[0].1.frames().len(), 4);
+}
@@ -0,0 +1,53 @@
+#![allow(dead_code)]
+
+use dynastes::{
+    state_machine::{
+        AnimationStateMachine, IndexSprite, Sprite, StateID, StateInstance, UpdateArgs,
+        UpdateReport,
+    },
+    states::{StandardData, StandardState},
+};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+/// A sprite that only remembers its index
+pub struct TestSprite(pub usize);
+
+impl Sprite for TestSprite {}
+
+impl IndexSprite for TestSprite {
+    fn set_index(&mut self, index: usize) {
+        self.0 = index;
+    }
+
+    fn get_index(&self) -> usize {
+        self.0
+    }
+}
+
+pub type Machine = AnimationStateMachine<TestSprite, StandardState<TestSprite>, ()>;
+pub type Instance = StateInstance<StandardState<TestSprite>, StandardData<TestSprite>>;
+
+pub fn id(id: &str) -> StateID {
+    StateID::from(id)
+}
+
+pub fn args(delta_ms: f64) -> UpdateArgs {
+    UpdateArgs { delta_ms }
+}
+
+/// Updates `instance` `count` times by `delta_ms`, collecting every report
+pub fn run(
+    machine: &Machine,
+    instance: &mut Instance,
+    sprite: &mut TestSprite,
+    delta_ms: f64,
+    count: usize,
+) -> UpdateReport {
+    let mut total = UpdateReport::default();
+    for _ in 0..count {
+        let report = machine.update(instance, args(delta_ms), sprite);
+        total.markers.extend(report.markers);
+        total.transitions.extend(report.transitions);
+    }
+    total
+}
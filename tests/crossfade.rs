@@ -0,0 +1,98 @@
+mod common;
+
+use common::{args, id, Machine, TestSprite};
+use dynastes::state_machine::{Condition, Marker, Transition};
+
+fn machine() -> Machine {
+    Machine::builder()
+        .state("walk", |s| {
+            s.frames(0..=3).mspf(100).marker(Marker::frame("step", 2))
+        })
+        .state("run", |s| s.frames(10..=13).mspf(100))
+        .transition(
+            Transition::new(id("walk"), id("run"))
+                .when(Condition::Bool {
+                    name: "running".to_string(),
+                    value: true,
+                })
+                .with_crossfade(200.),
+        )
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn crossfades_ramp_up_while_the_old_state_keeps_playing() {
+    let machine = machine();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    instance.parameters.set_bool("running", true);
+    machine.update(&mut instance, args(50.), &mut sprite);
+    assert_eq!(instance.current_id, id("run"));
+    let crossfade = instance.crossfade.as_ref().unwrap();
+    assert_eq!(crossfade.from_id, id("walk"));
+    assert_eq!(crossfade.weight(), 0.);
+
+    // (delta, weight, frame shown by "walk", frame shown by "run")
+    for (delta_ms, weight, from_index, index) in [(50., 0.25, 1, 10), (100., 0.75, 2, 11)] {
+        machine.update(&mut instance, args(delta_ms), &mut sprite);
+        let crossfade = instance.crossfade.as_ref().unwrap();
+        assert_eq!(crossfade.weight(), weight);
+        assert_eq!(crossfade.from_index, from_index);
+        // The current state has the last word on the sprite
+        assert_eq!(sprite.0, index);
+    }
+
+    machine.update(&mut instance, args(50.), &mut sprite);
+    assert!(instance.crossfade.is_none());
+    assert_eq!(sprite.0, 12);
+}
+
+#[test]
+fn markers_of_the_old_state_are_reported_during_the_crossfade() {
+    let machine = machine();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    instance.parameters.set_bool("running", true);
+    machine.update(&mut instance, args(50.), &mut sprite);
+    // "walk" shows frame 2 at 200ms, 150ms into the crossfade
+    let report = machine.update(&mut instance, args(149.), &mut sprite);
+    assert!(report.markers.is_empty());
+    let report = machine.update(&mut instance, args(1.), &mut sprite);
+    assert_eq!(report.markers.len(), 1);
+    assert_eq!(machine.id_at(report.markers[0].state), Some(&id("walk")));
+    assert_eq!(report.markers[0].name, "step");
+
+    // Once the crossfade is over "walk" is no longer played
+    machine.update(&mut instance, args(50.), &mut sprite);
+    assert!(instance.crossfade.is_none());
+    let mut reported = Vec::new();
+    for _ in 0..10 {
+        reported.extend(
+            machine
+                .update(&mut instance, args(100.), &mut sprite)
+                .markers,
+        );
+    }
+    assert!(reported.is_empty());
+}
+
+#[test]
+fn crossfades_without_a_duration_switch_instantly() {
+    let mut machine = machine();
+    machine.add_transitions(vec![
+        Transition::new(id("run"), id("walk")).with_crossfade(0.)
+    ]);
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    instance.parameters.set_bool("running", true);
+    machine.update(&mut instance, args(250.), &mut sprite);
+    instance.parameters.set_bool("running", false);
+    // Back to "walk", with the crossfade from "walk" to "run" dropped
+    machine.update(&mut instance, args(1.), &mut sprite);
+    assert_eq!(instance.current_id, id("walk"));
+    assert!(instance.crossfade.is_none());
+}
@@ -0,0 +1,215 @@
+mod common;
+
+use common::{args, id, Machine, TestSprite};
+use dynastes::{
+    error::{Error, ValidationError},
+    state_machine::{AlreadyPlaying, AnimationState, Marker, StableHash, Transition},
+    states::index::{FrameRate, IndexState, IndexStateBuilder},
+};
+
+#[test]
+fn missing_targets_fail_before_the_instance_changes() {
+    let mut machine = Machine::builder()
+        .state("idle", |s| {
+            s.frames(0..=3).mspf(100).marker(Marker::frame("step", 1))
+        })
+        .build()
+        .unwrap();
+    machine.add_transitions(vec![Transition::new(id("idle"), id("missing"))]);
+
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+    let before = instance.checksum();
+    let error = machine
+        .try_update(&mut instance, args(150.), &mut sprite)
+        .unwrap_err();
+
+    assert_eq!(
+        error,
+        Error::MissingTarget {
+            from: id("idle"),
+            to: id("missing"),
+        }
+    );
+    assert_eq!(instance.checksum(), before);
+    assert_eq!(sprite, TestSprite::default());
+}
+
+#[test]
+fn updates_stop_in_a_state_with_missing_targets() {
+    let mut machine = Machine::builder()
+        .state("intro", |s| s.frames(0..=1).mspf(100).then("idle"))
+        .state("idle", |s| s.frames(2..=3).mspf(100))
+        .build()
+        .unwrap();
+    machine.add_states(vec![(
+        id("idle"),
+        IndexState::new(2, 3, 100., Some(id("missing")), None, None).into(),
+    )]);
+
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+    let report = machine
+        .try_update(&mut instance, args(500.), &mut sprite)
+        .unwrap();
+    assert_eq!(report.transitions.len(), 1);
+    assert_eq!(instance.current_id, id("idle"));
+
+    let before = instance.checksum();
+    assert!(machine
+        .try_update(&mut instance, args(100.), &mut sprite)
+        .is_err());
+    assert_eq!(instance.checksum(), before);
+}
+
+#[test]
+fn missing_queued_states_fail_before_the_instance_changes() {
+    let machine = Machine::builder()
+        .state("idle", |s| s.frames(0..=3).mspf(100))
+        .build()
+        .unwrap();
+    let mut instance = machine.default_instance();
+    instance.queued = Some(id("missing"));
+
+    let before = instance.checksum();
+    let mut sprite = TestSprite::default();
+    assert!(machine
+        .try_update(&mut instance, args(1000.), &mut sprite)
+        .is_err());
+    assert_eq!(instance.checksum(), before);
+}
+
+#[test]
+fn index_states_with_a_zero_mspf_fail_validation() {
+    let state = IndexState::<TestSprite>::new(0, 3, 0., None, None, None);
+    assert_eq!(
+        state.validate(&id("idle"), None),
+        [ValidationError::InvalidFrameRate(id("idle"))]
+    );
+    let machine = Machine::with_default((), id("idle"), state.into());
+    assert_eq!(
+        machine.validate(None).unwrap_err(),
+        Error::Invalid(vec![ValidationError::InvalidFrameRate(id("idle"))])
+    );
+}
+
+#[test]
+fn invalid_frame_rates_fail_validation_and_never_advance() {
+    let state = IndexState::new(0, 3, 100., None, None, None)
+        .with_frame_rate(FrameRate::Mspf(0.))
+        .with_markers(vec![Marker::frame("step", 1)]);
+    let machine = Machine::with_default((), id("idle"), state.into());
+    assert!(machine.validate(None).is_err());
+
+    // Skipping validation must not hang the update
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+    let report = machine.update(&mut instance, args(1000.), &mut sprite);
+    assert!(report.markers.is_empty());
+}
+
+#[test]
+fn negative_infinite_and_reversed_phases_fail_validation() {
+    let invalid = [ValidationError::InvalidPhase(id("idle"))];
+    for phase in [-1., f64::INFINITY, f64::NAN] {
+        let state = IndexState::<TestSprite>::new(0, 3, 100., None, Some(phase), None);
+        assert_eq!(state.validate(&id("idle"), None), invalid, "{phase}");
+    }
+    for (min_ms, max_ms) in [(200., 100.), (-100., 100.), (0., f64::INFINITY)] {
+        let state = IndexState::<TestSprite>::new(0, 3, 100., None, None, None)
+            .with_random_phase(min_ms, max_ms);
+        assert_eq!(
+            state.validate(&id("idle"), None),
+            invalid,
+            "{min_ms}..{max_ms}"
+        );
+        let built = IndexState::<TestSprite>::builder()
+            .frames(0..=3)
+            .mspf(100)
+            .random_phase(min_ms, max_ms)
+            .build(&id("idle"));
+        assert_eq!(built.unwrap_err(), invalid[0]);
+    }
+
+    // An empty range always picks its minimum
+    let state =
+        IndexState::<TestSprite>::new(0, 3, 100., None, None, None).with_random_phase(100., 100.);
+    assert!(state.validate(&id("idle"), None).is_empty());
+}
+
+#[test]
+fn playing_from_a_missing_state_fails() {
+    let machine = Machine::builder()
+        .state("idle", |s| s.frames(0..=3).mspf(100))
+        .build()
+        .unwrap();
+    let mut instance = machine.default_instance();
+    instance.current_id = id("missing");
+
+    let error = machine
+        .play(&mut instance, id("idle"), AlreadyPlaying::Restart)
+        .unwrap_err();
+    assert_eq!(error, Error::MissingState(id("missing")));
+    assert_eq!(instance.current_id, id("missing"));
+}
+
+#[test]
+fn builders_report_invalid_frame_rates() {
+    let invalid = Error::Invalid(vec![ValidationError::InvalidFrameRate(id("idle"))]);
+    let frame_rates: [fn(_) -> _; 5] = [
+        |s: IndexStateBuilder<TestSprite>| s.fps(1e-310),
+        |s| s.fps(0),
+        |s| s.mspf(-1),
+        |s| s.mspf(f64::NAN),
+        |s| s.duration(f64::INFINITY),
+    ];
+    for (i, frame_rate) in frame_rates.into_iter().enumerate() {
+        let error = Machine::builder()
+            .state("idle", |s| frame_rate(s.frames(0..=3)))
+            .build()
+            .unwrap_err();
+        assert_eq!(error, invalid, "frame rate {i}");
+    }
+
+    // The frame durations are checked too, and so is a state made without the builder
+    let state = IndexState::<TestSprite>::new(0, 3, 100., None, None, None)
+        .with_frame_rate(FrameRate::Fps(1e-310));
+    assert_eq!(
+        state.validate(&id("idle"), None),
+        [ValidationError::InvalidFrameRate(id("idle"))]
+    );
+}
+
+#[test]
+fn builders_report_duplicate_states() {
+    let error = Machine::builder()
+        .state("idle", |s| s.frames(0..=3).mspf(100))
+        .state("idle", |s| s.frames(4..=7).mspf(100))
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        error,
+        Error::Invalid(vec![ValidationError::DuplicateState(id("idle"))])
+    );
+}
+
+#[test]
+fn builders_report_having_no_states() {
+    let error = Machine::builder().build().unwrap_err();
+    assert_eq!(error, Error::Invalid(vec![ValidationError::NoStates]));
+}
+
+#[test]
+fn builders_report_unknown_next_states() {
+    let error = Machine::builder()
+        .state("intro", |s| s.frames(0..=3).mspf(100).then("missing"))
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        error,
+        Error::Invalid(vec![ValidationError::MissingTarget {
+            from: id("intro"),
+            to: id("missing"),
+        }])
+    );
+}
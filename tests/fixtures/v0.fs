@@ -0,0 +1 @@
+BevyFrameSource(path:"sprite-sheet.png",metadata:TextureAtlasGridMetadata(tile_size:Vec2(128.0,128.0),columns:26,rows:2,padding:None,offset:None))
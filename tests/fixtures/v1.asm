@@ -0,0 +1,49 @@
+BevyASMSerde(
+    frame_source:"sprite-sheet.fs",
+    default_id:("idle"),
+    states:[
+        (
+            ("walk"),
+            Index((
+                min_i:0,
+                max_i:9,
+                nominal_mspf:66.66666666667,
+                actual_mspf:133.33333333334,
+                next_state:Some(("idle")),
+                mode:PingPong,
+                loops:2,
+                phase:0.0,
+                random_phase:None,
+                frames_per_increment:2.0,
+                fluidity:0.5,
+                markers:[(name:"step",at:Frame(4))],
+            ))
+        ),
+        (
+            ("idle"),
+            Index((
+                min_i:26,
+                max_i:51,
+                nominal_mspf:66.66666666667,
+                actual_mspf:66.66666666667,
+                next_state:Some(("walk")),
+                phase:0.0,
+                frames_per_increment:1.0,
+                fluidity:1.0,
+            ))
+        ),
+        (
+            ("wave"),
+            FrameList((
+                frames:[(index:10,duration_ms:100.0),(index:12,duration_ms:60.0)],
+                next_state:Some(("idle")),
+            ))
+        ),
+    ],
+    groups:[
+        (("moving"), (default_id:("walk"),members:[("walk"),("wave")])),
+    ],
+    transitions:[
+        (from:("idle"),to:("wave"),conditions:[Bool(name:"waving",value:true)]),
+    ],
+)
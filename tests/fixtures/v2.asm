@@ -0,0 +1,29 @@
+BevyASMSerde(
+    version:2,
+    frame_source:"sprite-sheet.fs",
+    default_id:("idle"),
+    states:[
+        (
+            ("walk"),
+            Index((
+                min_i:0,
+                max_i:9,
+                frame_rate:Fps(15.0),
+                next_state:Some(("idle")),
+                phase:0.0,
+                fluidity:1.0,
+            ))
+        ),
+        (
+            ("idle"),
+            Index((
+                min_i:26,
+                max_i:51,
+                frame_rate:Fps(15.0),
+                next_state:Some(("walk")),
+                phase:0.0,
+                fluidity:1.0
+            ))
+        )
+    ]
+)
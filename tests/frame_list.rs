@@ -0,0 +1,79 @@
+mod common;
+
+use common::{args, id, Machine, TestSprite};
+use dynastes::{
+    error::ValidationError,
+    state_machine::AnimationState,
+    states::frame_list::{FrameListState, FrameListStateBuilder},
+};
+
+fn machine(frames: FrameListStateBuilder<TestSprite>) -> Machine {
+    Machine::builder()
+        .frame_list("anim", |_| frames)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn frames_stay_for_their_own_duration() {
+    let machine = machine(
+        FrameListState::builder()
+            .frame(3, 100)
+            .frame(7, 50)
+            .frame(3, 25),
+    );
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    let mut indices = Vec::new();
+    for _ in 0..8 {
+        machine.update(&mut instance, args(25.), &mut sprite);
+        indices.push(sprite.0);
+    }
+    assert_eq!(indices, [3, 3, 3, 7, 7, 3, 3, 3]);
+}
+
+#[test]
+fn the_last_frame_plays_in_full_before_moving_on() {
+    let machine = Machine::builder()
+        .frame_list("wave", |s| s.frame(1, 100).frame(2, 60).then("idle"))
+        .state("idle", |s| s.frames(10..=11).mspf(100))
+        .build()
+        .unwrap();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    machine.update(&mut instance, args(159.), &mut sprite);
+    assert_eq!((instance.current_id.clone(), sprite.0), (id("wave"), 2));
+    let report = machine.update(&mut instance, args(1.), &mut sprite);
+    assert_eq!(report.transitions.len(), 1);
+    assert_eq!((instance.current_id.clone(), sprite.0), (id("idle"), 10));
+}
+
+#[test]
+fn invalid_durations_are_reported() {
+    let state = FrameListState::<TestSprite>::new(vec![(0, 100.), (1, f64::NAN)], None);
+    assert_eq!(
+        state.validate(&id("anim"), None),
+        [ValidationError::InvalidFrameDuration {
+            state: id("anim"),
+            position: 1,
+        }]
+    );
+    assert!(FrameListState::<TestSprite>::builder()
+        .frame(0, f64::INFINITY)
+        .build(&id("anim"))
+        .is_err());
+}
+
+#[test]
+fn invalid_durations_never_advance() {
+    let state = FrameListState::new(vec![(0, 100.), (1, f64::NAN)], None);
+    let machine = Machine::with_default((), id("anim"), state.into());
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    // Skipping validation must not hang the update
+    machine.update(&mut instance, args(1000.), &mut sprite);
+    assert_eq!(sprite.0, 0);
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::{args, id, Instance, Machine, TestSprite};
+use dynastes::state_machine::{AlreadyPlaying, Condition, Transition};
+
+/// "ground" holds the "loco" group and "jump", and "loco" holds "idle" and "walk"
+fn machine() -> Machine {
+    Machine::builder()
+        .state("idle", |s| s.frames(0..=3).mspf(100))
+        .state("walk", |s| s.frames(10..=13).mspf(100))
+        .state("jump", |s| s.frames(20..=23).mspf(100).then("loco"))
+        .state("attack", |s| s.frames(30..=32).mspf(100).then("ground"))
+        .group("ground", "loco", ["loco", "jump"])
+        .group("loco", "idle", ["idle", "walk"])
+        .default_state("ground")
+        .transition(
+            Transition::new(id("ground"), id("attack")).when(Condition::Trigger("attack".into())),
+        )
+        .build()
+        .unwrap()
+}
+
+fn path(instance: &Instance) -> Vec<&str> {
+    instance.path().map(|id| id.0.as_str()).collect()
+}
+
+#[test]
+fn groups_are_entered_through_their_default_member() {
+    let machine = machine();
+    assert_eq!(machine.resolve(&id("ground")), Ok(&id("idle")));
+    assert_eq!(machine.index_of(&id("loco")), machine.index_of(&id("idle")));
+
+    let instance = machine.default_instance();
+    assert_eq!(instance.current_id, id("idle"));
+    let instance = machine.try_new_instance(id("loco")).unwrap();
+    assert_eq!(instance.current_id, id("idle"));
+}
+
+#[test]
+fn instances_know_their_active_path() {
+    let machine = machine();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+    assert_eq!(path(&instance), ["ground", "loco", "idle"]);
+    assert_eq!(machine.groups_of(&id("walk")), [id("ground"), id("loco")]);
+
+    machine
+        .play(&mut instance, id("jump"), AlreadyPlaying::Restart)
+        .unwrap();
+    assert_eq!(path(&instance), ["ground", "jump"]);
+    assert!(instance.is_active(&id("ground")));
+    assert!(!instance.is_active(&id("loco")));
+
+    // "jump" moves on to the "loco" group, which enters "idle"
+    machine.update(&mut instance, args(300.), &mut sprite);
+    assert_eq!(path(&instance), ["ground", "loco", "idle"]);
+}
+
+#[test]
+fn transitions_out_of_a_group_leave_every_member() {
+    let machine = machine();
+    for state in ["idle", "walk", "jump"] {
+        let mut instance = machine.try_new_instance(id(state)).unwrap();
+        let mut sprite = TestSprite::default();
+        instance.parameters.set_trigger("attack");
+        machine.update(&mut instance, args(10.), &mut sprite);
+        assert_eq!(instance.current_id, id("attack"), "{state}");
+        assert_eq!(path(&instance), ["attack"], "{state}");
+    }
+
+    // And states moving on to a group enter its default member
+    let mut instance = machine.try_new_instance(id("attack")).unwrap();
+    let mut sprite = TestSprite::default();
+    machine.update(&mut instance, args(200.), &mut sprite);
+    assert_eq!(instance.current_id, id("idle"));
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use common::{args, id, Machine, TestSprite};
+use dynastes::{
+    error::Error,
+    state_machine::{
+        Condition, Layer, LayerMode, LayeredStateMachine, Transition, TransitionReason,
+    },
+    states::StandardState,
+};
+
+type Layered = LayeredStateMachine<TestSprite, StandardState<TestSprite>, ()>;
+
+fn body() -> Machine {
+    Machine::builder()
+        .state("idle", |s| s.frames(0..=3).mspf(100))
+        .state("walk", |s| s.frames(10..=13).mspf(100))
+        .state("jump", |s| s.frames(20..=23).mspf(100))
+        .transition(
+            Transition::new(id("idle"), id("walk")).when(Condition::Bool {
+                name: "moving".to_string(),
+                value: true,
+            }),
+        )
+        .transition(
+            Transition::new(id("walk"), id("jump")).when(Condition::Trigger("jump".to_string())),
+        )
+        .build()
+        .unwrap()
+}
+
+/// Arms without a "jump" state, whose walk cycle is twice as fast as the body's
+fn arms() -> Machine {
+    Machine::builder()
+        .state("idle", |s| s.frames(30..=33).mspf(100))
+        .state("walk", |s| s.frames(40..=43).mspf(50))
+        .state("wave", |s| s.frames(50..=51).mspf(100))
+        .transition(
+            Transition::new(id("idle"), id("wave")).when(Condition::Trigger("wave".to_string())),
+        )
+        .build()
+        .unwrap()
+}
+
+fn layered(arms: Layer<TestSprite, StandardState<TestSprite>, ()>) -> Layered {
+    let mut machine = LayeredStateMachine::new(Layer::new("body", body()));
+    machine.add_layer(arms);
+    machine
+}
+
+#[test]
+fn overriding_layers_ignore_their_base() {
+    let machine = layered(Layer::new("arms", arms()));
+    let mut instance = machine.default_instance();
+    let mut sprites = [TestSprite::default(); 2];
+
+    instance.parameters.set_bool("moving", true);
+    let reports = machine.update(&mut instance, args(10.), &mut sprites);
+    assert_eq!(instance.layers[0].current_id, id("walk"));
+    assert_eq!(instance.layers[1].current_id, id("idle"));
+    assert!(reports[1].transitions.is_empty());
+}
+
+#[test]
+fn following_layers_play_the_state_of_their_base() {
+    let machine = layered(Layer::new("arms", arms()).with_mode(LayerMode::Follow));
+    let mut instance = machine.default_instance();
+    let mut sprites = [TestSprite::default(); 2];
+
+    instance.parameters.set_bool("moving", true);
+    let reports = machine.update(&mut instance, args(10.), &mut sprites);
+    assert_eq!(instance.layers[1].current_id, id("walk"));
+    assert_eq!(reports[1].transitions.len(), 1);
+    assert_eq!(reports[1].transitions[0].reason, TransitionReason::Followed);
+
+    // Already following, so the layer keeps playing
+    let reports = machine.update(&mut instance, args(60.), &mut sprites);
+    assert!(reports[1].transitions.is_empty());
+    assert_eq!(sprites[1].0, 41);
+}
+
+#[test]
+fn following_layers_keep_their_state_when_the_base_state_is_missing() {
+    let machine = layered(Layer::new("arms", arms()).with_mode(LayerMode::Follow));
+    let mut instance = machine.default_instance();
+    let mut sprites = [TestSprite::default(); 2];
+
+    instance.parameters.set_bool("moving", true);
+    machine.update(&mut instance, args(10.), &mut sprites);
+    instance.parameters.set_trigger("jump");
+    let reports = machine.update(&mut instance, args(10.), &mut sprites);
+    assert_eq!(instance.layers[0].current_id, id("jump"));
+    assert_eq!(instance.layers[1].current_id, id("walk"));
+    assert!(reports[1].transitions.is_empty());
+
+    // While the base is in a state the layer doesn't have, its own transitions are taken
+    let machine = layered(Layer::new("arms", arms()).with_mode(LayerMode::Follow));
+    let mut instance = machine.default_instance();
+    instance.layers[0] = machine.layers()[0]
+        .machine
+        .try_new_instance(id("jump"))
+        .unwrap();
+    instance.parameters.set_trigger("wave");
+    let reports = machine.update(&mut instance, args(10.), &mut sprites);
+    assert_eq!(instance.layers[1].current_id, id("wave"));
+    assert_eq!(
+        reports[1].transitions[0].reason,
+        TransitionReason::Conditional
+    );
+}
+
+#[test]
+fn synced_layers_keep_the_normalized_time_of_their_base() {
+    let machine = layered(
+        Layer::new("arms", arms())
+            .with_mode(LayerMode::Follow)
+            .synced(),
+    );
+    let mut instance = machine.default_instance();
+    let mut sprites = [TestSprite::default(); 2];
+
+    instance.parameters.set_bool("moving", true);
+    for _ in 0..20 {
+        machine.update(&mut instance, args(35.), &mut sprites);
+        let [body, arms] = &instance.layers[..] else {
+            unreachable!()
+        };
+        let base_time = machine.layers()[0].machine.normalized_time(body).unwrap();
+        let time = machine.layers()[1].machine.normalized_time(arms).unwrap();
+        assert!((time - base_time).abs() < 1e-9, "{time} {base_time}");
+    }
+    // 665ms of walking: 265ms into the body's 400ms cycle, and 132.5ms into the arms' 200ms one
+    assert_eq!(sprites, [TestSprite(12), TestSprite(42)]);
+}
+
+#[test]
+fn layers_need_a_sprite_each() {
+    let machine = layered(Layer::new("arms", arms()));
+    let mut instance = machine.default_instance();
+    let mut sprites = [TestSprite::default(); 1];
+    assert_eq!(
+        machine.try_update(&mut instance, args(10.), &mut sprites),
+        Err(Error::LayerMismatch {
+            layers: 2,
+            found: 1
+        })
+    );
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use common::{args, id, run, Machine, TestSprite};
+use dynastes::{
+    state_machine::AnimationState,
+    states::index::{IndexState, PlaybackMode},
+};
+
+/// A 0..=3 spin at 100 mspf in `mode` that moves to "idle" after `loops` loops
+fn spin(mode: PlaybackMode, loops: usize) -> Machine {
+    Machine::builder()
+        .state("spin", |s| {
+            s.frames(0..=3)
+                .mspf(100)
+                .mode(mode)
+                .loops(loops)
+                .then("idle")
+        })
+        .state("idle", |s| s.frames(10..=11).mspf(100))
+        .build()
+        .unwrap()
+}
+
+/// The ms after which the machine leaves "spin", in 1ms updates
+fn spin_ms(machine: &Machine) -> usize {
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+    (1..=10_000)
+        .find(|_| {
+            machine.update(&mut instance, args(1.), &mut sprite);
+            instance.current_id == id("idle")
+        })
+        .expect("the spin never ended")
+}
+
+#[test]
+fn forward_loops_end_on_the_last_frame_of_the_last_loop() {
+    // The last frame is reached at 300ms, then every 400ms
+    assert_eq!(spin_ms(&spin(PlaybackMode::Forward, 1)), 300);
+    assert_eq!(spin_ms(&spin(PlaybackMode::Forward, 3)), 1100);
+}
+
+#[test]
+fn reverse_loops_end_on_the_first_frame_of_the_last_loop() {
+    assert_eq!(spin_ms(&spin(PlaybackMode::Reverse, 2)), 700);
+}
+
+#[test]
+fn ping_pong_loops_end_back_on_the_first_frame() {
+    // A ping-pong loop is 600ms, turning around at 300ms
+    assert_eq!(spin_ms(&spin(PlaybackMode::PingPong, 1)), 600);
+    assert_eq!(spin_ms(&spin(PlaybackMode::PingPong, 2)), 1200);
+}
+
+#[test]
+fn zero_loops_end_like_one() {
+    assert_eq!(spin_ms(&spin(PlaybackMode::Forward, 0)), 300);
+}
+
+#[test]
+fn loops_without_a_next_state_play_forever() {
+    let machine = Machine::builder()
+        .state("spin", |s| s.frames(0..=3).mspf(100).loops(3))
+        .build()
+        .unwrap();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    let report = run(&machine, &mut instance, &mut sprite, 50., 200);
+    assert!(report.transitions.is_empty());
+    assert_eq!(instance.current_id, id("spin"));
+}
+
+#[test]
+fn negative_deltas_are_ignored() {
+    let machine = spin(PlaybackMode::Forward, 3);
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+    machine.update(&mut instance, args(450.), &mut sprite);
+    assert_eq!(sprite.0, 0);
+
+    for delta_ms in [-450., -1., f64::NAN, f64::NEG_INFINITY, f64::INFINITY] {
+        let report = machine.update(&mut instance, args(delta_ms), &mut sprite);
+        assert!(report.is_empty());
+        assert_eq!(sprite.0, 0);
+    }
+    // Still 650ms away from the end of the third loop
+    machine.update(&mut instance, args(649.), &mut sprite);
+    assert_eq!(instance.current_id, id("spin"));
+    machine.update(&mut instance, args(1.), &mut sprite);
+    assert_eq!(instance.current_id, id("idle"));
+}
+
+#[test]
+fn states_played_backwards_do_not_panic() {
+    // Only the state machine ignores negative deltas, a state updated directly plays back
+    let state =
+        IndexState::<TestSprite>::new(0, 3, 100., Some(id("idle")), None, None).with_loops(3);
+    let mut data = state.start();
+    let mut sprite = TestSprite::default();
+    let mut markers = Vec::new();
+
+    state.update(&mut data, args(450.), &mut sprite, &mut markers);
+    state.update(&mut data, args(-450.), &mut sprite, &mut markers);
+    assert_eq!(state.next_state(&data), None);
+}
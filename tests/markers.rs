@@ -0,0 +1,89 @@
+mod common;
+
+use common::{args, id, run, Machine, TestSprite};
+use dynastes::state_machine::Marker;
+
+fn names(report: &dynastes::state_machine::UpdateReport) -> Vec<&str> {
+    report
+        .markers
+        .iter()
+        .map(|marker| marker.name.as_str())
+        .collect()
+}
+
+#[test]
+fn frame_markers_are_reported_every_time_their_frame_is_shown() {
+    let machine = Machine::builder()
+        .state("walk", |s| {
+            s.frames(0..=3)
+                .mspf(100)
+                .marker(Marker::frame("left", 0))
+                .marker(Marker::frame("right", 2))
+        })
+        .build()
+        .unwrap();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    // The first frame is shown as soon as the state starts
+    let report = machine.update(&mut instance, args(50.), &mut sprite);
+    assert_eq!(names(&report), ["left"]);
+    let report = run(&machine, &mut instance, &mut sprite, 100., 8);
+    assert_eq!(names(&report), ["right", "left", "right", "left"]);
+    assert!(report
+        .markers
+        .iter()
+        .all(|marker| machine.id_at(marker.state) == Some(&id("walk"))));
+}
+
+#[test]
+fn time_markers_are_reported_once_per_loop() {
+    let machine = Machine::builder()
+        .state("walk", |s| {
+            s.frames(0..=3).mspf(100).marker(Marker::time("step", 150.))
+        })
+        .build()
+        .unwrap();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    // Reported by the update that plays the marker's time, which reaching it doesn't yet
+    let report = machine.update(&mut instance, args(150.), &mut sprite);
+    assert!(report.markers.is_empty());
+    let report = machine.update(&mut instance, args(1.), &mut sprite);
+    assert_eq!(names(&report), ["step"]);
+    // Two more loops of 400ms in a single update
+    let report = machine.update(&mut instance, args(800.), &mut sprite);
+    assert_eq!(names(&report), ["step", "step"]);
+}
+
+#[test]
+fn markers_of_every_state_passed_through_are_reported() {
+    let machine = Machine::builder()
+        .frame_list("jump", |s| {
+            s.frame(0, 100)
+                .frame(1, 100)
+                .marker(Marker::frame("takeoff", 1))
+                .then("land")
+        })
+        .frame_list("land", |s| {
+            s.frame(2, 100)
+                .frame(3, 100)
+                .marker(Marker::frame("thud", 2))
+        })
+        .build()
+        .unwrap();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    let report = machine.update(&mut instance, args(250.), &mut sprite);
+    let events: Vec<_> = report
+        .markers
+        .iter()
+        .map(|marker| {
+            let state = machine.id_at(marker.state).unwrap();
+            (state.0.as_str(), marker.name.as_str())
+        })
+        .collect();
+    assert_eq!(events, [("jump", "takeoff"), ("land", "thud")]);
+}
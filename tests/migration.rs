@@ -0,0 +1,150 @@
+use dynastes::{
+    bevy::{
+        bevy_serde::BevyASMSerde,
+        migration::{asm_from_ron, frame_source_from_ron, ASM_VERSION, FRAME_SOURCE_VERSION},
+        BevyState,
+    },
+    error::Error,
+    state_machine::{AnimationState, AnimationStateMachine, StateID},
+    states::{
+        index::{FrameRate, IndexState, PlaybackMode},
+        StandardState,
+    },
+};
+
+const MSPF: f64 = 66.66666666667;
+
+fn load(name: &str) -> BevyASMSerde {
+    let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+    let source = std::fs::read_to_string(&path).unwrap();
+    asm_from_ron(&source).unwrap_or_else(|e| panic!("{path}: {e}"))
+}
+
+fn index_state<'a>(
+    asm: &'a BevyASMSerde,
+    id: &str,
+) -> &'a IndexState<bevy::sprite::TextureAtlasSprite> {
+    match asm.states.get(&StateID::from(id)) {
+        Some(StandardState::Index(state)) => state,
+        other => panic!("expected \"{id}\" to be an index state, found {other:?}"),
+    }
+}
+
+fn validate(asm: BevyASMSerde) {
+    let mut machine: AnimationStateMachine<_, BevyState, ()> =
+        AnimationStateMachine::with_states((), asm.default_id, asm.states);
+    machine.add_groups(asm.groups.into_iter().collect());
+    machine.add_transitions(asm.transitions);
+    machine.add_any_state_transitions(asm.any_state_transitions);
+    machine.validate(Some(52)).unwrap();
+}
+
+#[test]
+fn every_asm_version_upgrades_to_the_current_one() {
+    for name in ["v0.asm", "v1.asm", "v2.asm"] {
+        let asm = load(name);
+        assert_eq!(asm.version, ASM_VERSION, "{name}");
+        assert_eq!(asm.frame_source, "sprite-sheet.fs", "{name}");
+        assert_eq!(asm.default_id, StateID::from("idle"), "{name}");
+
+        let idle = index_state(&asm, "idle");
+        // Later versions write the frame rate as 15 fps rather than its rounded ms per frame.
+        // The state moves on when it reaches the last of its 26 frames.
+        let length_ms = idle.length_ms().unwrap();
+        assert!((length_ms - 25. * MSPF).abs() < 1e-6, "{name}: {length_ms}");
+        validate(asm);
+    }
+}
+
+#[test]
+fn v0_states_become_index_states() {
+    let asm = load("v0.asm");
+    let walk = index_state(&asm, "walk");
+    assert_eq!(walk.frame_rate(), FrameRate::Mspf(MSPF));
+    // The state moves on when it reaches the last of its 10 frames
+    assert_eq!(walk.length_ms(), Some(9. * MSPF));
+    assert!(asm.groups.is_empty());
+    assert!(asm.transitions.is_empty());
+}
+
+#[test]
+fn v1_keeps_its_settings_and_recomputes_the_rest() {
+    let asm = load("v1.asm");
+    let walk = index_state(&asm, "walk");
+    assert_eq!(walk.frame_rate(), FrameRate::Mspf(MSPF));
+    assert_eq!(walk.mode(), PlaybackMode::PingPong);
+    assert_eq!(walk.loops(), 2);
+    assert_eq!(walk.markers().len(), 1);
+    // Two ping-pongs over 10 frames
+    assert_eq!(walk.length_ms(), Some(36. * MSPF));
+
+    assert!(matches!(
+        asm.states.get(&StateID::from("wave")),
+        Some(StandardState::FrameList(_))
+    ));
+    assert_eq!(asm.groups.len(), 1);
+    assert_eq!(asm.transitions.len(), 1);
+}
+
+#[test]
+fn upgraded_documents_are_written_in_the_current_version() {
+    let upgraded = ron::to_string(&load("v1.asm")).unwrap();
+    let reloaded = asm_from_ron(&upgraded).unwrap();
+    assert_eq!(ron::to_string(&reloaded).unwrap(), upgraded);
+}
+
+#[test]
+fn explicit_older_versions_are_upgraded() {
+    for (name, version) in [("v0.asm", 0), ("v1.asm", 1)] {
+        let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+        let source = std::fs::read_to_string(path).unwrap().replacen(
+            "BevyASMSerde(",
+            &format!("BevyASMSerde(version:{version},"),
+            1,
+        );
+        let asm = asm_from_ron(&source).unwrap_or_else(|e| panic!("{name}: {e}"));
+        assert_eq!(
+            ron::to_string(&asm).unwrap(),
+            ron::to_string(&load(name)).unwrap(),
+            "{name}"
+        );
+    }
+
+    let path = format!("{}/tests/fixtures/v0.fs", env!("CARGO_MANIFEST_DIR"));
+    let source = std::fs::read_to_string(path).unwrap().replacen(
+        "BevyFrameSource(",
+        "BevyFrameSource(version:0,",
+        1,
+    );
+    let frame_source = frame_source_from_ron(&source).unwrap();
+    assert_eq!(frame_source.version, FRAME_SOURCE_VERSION);
+    assert_eq!(frame_source.metadata.frame_count(), 52);
+}
+
+#[test]
+fn newer_versions_are_rejected() {
+    let source =
+        r#"BevyASMSerde(version:99,frame_source:"sprite-sheet.fs",default_id:("idle"),states:[])"#;
+    assert_eq!(
+        asm_from_ron(source).unwrap_err(),
+        Error::UnsupportedVersion {
+            found: 99,
+            latest: ASM_VERSION
+        }
+    );
+}
+
+#[test]
+fn every_frame_source_version_upgrades_to_the_current_one() {
+    for name in ["v0.fs", "v1.fs"] {
+        let path = format!("{}/tests/fixtures/{name}", env!("CARGO_MANIFEST_DIR"));
+        let frame_source = frame_source_from_ron(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(frame_source.version, FRAME_SOURCE_VERSION, "{name}");
+        assert_eq!(
+            frame_source.path.to_str(),
+            Some("sprite-sheet.png"),
+            "{name}"
+        );
+        assert_eq!(frame_source.metadata.frame_count(), 52, "{name}");
+    }
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::{args, id, Machine, TestSprite};
+use dynastes::state_machine::{AlreadyPlaying, TransitionReason};
+
+fn machine() -> Machine {
+    Machine::builder()
+        .state("idle", |s| s.frames(0..=3).mspf(100))
+        .state("walk", |s| s.frames(10..=13).mspf(100))
+        .state("attack", |s| s.frames(20..=22).mspf(100).then("idle"))
+        .group("loco", "idle", ["idle", "walk"])
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn continuing_an_active_group_keeps_the_current_state() {
+    let machine = machine();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    machine
+        .play(&mut instance, id("walk"), AlreadyPlaying::Continue)
+        .unwrap();
+    machine.update(&mut instance, args(150.), &mut sprite);
+    let played = machine
+        .play(&mut instance, id("loco"), AlreadyPlaying::Continue)
+        .unwrap();
+    assert_eq!(played, None);
+    assert_eq!(instance.current_id, id("walk"));
+    assert_eq!(machine.elapsed_ms(&instance), Some(150.));
+
+    // Restarting a group enters its default state
+    let played = machine
+        .play(&mut instance, id("loco"), AlreadyPlaying::Restart)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        (machine.id_at(played.from), machine.id_at(played.to)),
+        (Some(&id("walk")), Some(&id("idle")))
+    );
+    assert_eq!(played.reason, TransitionReason::Played);
+}
+
+#[test]
+fn continuing_keeps_the_queued_state() {
+    let machine = machine();
+    let mut instance = machine.default_instance();
+
+    machine
+        .play_queued(&mut instance, id("attack"), AlreadyPlaying::Restart)
+        .unwrap();
+    for state in ["idle", "loco"] {
+        let played = machine
+            .play(&mut instance, id(state), AlreadyPlaying::Continue)
+            .unwrap();
+        assert_eq!(played, None);
+        assert_eq!(instance.queued, Some(id("attack")));
+    }
+
+    // Anything that changes the state drops it
+    machine
+        .play(&mut instance, id("walk"), AlreadyPlaying::Continue)
+        .unwrap();
+    assert_eq!(instance.queued, None);
+}
+
+#[test]
+fn queueing_an_active_group_is_ignored_when_continuing() {
+    let machine = machine();
+    let mut instance = machine.default_instance();
+
+    machine
+        .play_queued(&mut instance, id("loco"), AlreadyPlaying::Continue)
+        .unwrap();
+    assert_eq!(instance.queued, None);
+    machine
+        .play_queued(&mut instance, id("loco"), AlreadyPlaying::Restart)
+        .unwrap();
+    assert_eq!(instance.queued, Some(id("idle")));
+}
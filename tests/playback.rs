@@ -0,0 +1,66 @@
+mod common;
+
+use common::{args, Machine, TestSprite};
+use dynastes::states::index::{IndexState, PlaybackMode};
+
+/// The sprite index after each of `count` 100ms updates of a 0..=3 state at 100 mspf in `mode`
+fn indices(mode: PlaybackMode, count: usize) -> Vec<usize> {
+    let machine = Machine::with_default(
+        (),
+        "anim".into(),
+        IndexState::new(0, 3, 100., None, None, None)
+            .with_mode(mode)
+            .into(),
+    );
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+    (0..count)
+        .map(|_| {
+            machine.update(&mut instance, args(100.), &mut sprite);
+            sprite.0
+        })
+        .collect()
+}
+
+#[test]
+fn forward_loops_back_to_the_first_frame() {
+    assert_eq!(indices(PlaybackMode::Forward, 6), [1, 2, 3, 0, 1, 2]);
+}
+
+#[test]
+fn reverse_plays_from_the_last_frame() {
+    assert_eq!(indices(PlaybackMode::Reverse, 6), [2, 1, 0, 3, 2, 1]);
+}
+
+#[test]
+fn ping_pong_turns_around_at_both_ends() {
+    assert_eq!(indices(PlaybackMode::PingPong, 8), [1, 2, 3, 2, 1, 0, 1, 2]);
+}
+
+#[test]
+fn once_holds_the_last_frame() {
+    assert_eq!(indices(PlaybackMode::Once, 6), [1, 2, 3, 3, 3, 3]);
+}
+
+#[test]
+fn once_moves_on_after_the_last_frame() {
+    let machine = Machine::builder()
+        .state("attack", |s| {
+            s.frames(0..=3)
+                .mspf(100)
+                .mode(PlaybackMode::Once)
+                .then("idle")
+        })
+        .state("idle", |s| s.frames(10..=11).mspf(100))
+        .build()
+        .unwrap();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    let report = machine.update(&mut instance, args(299.), &mut sprite);
+    assert!(report.transitions.is_empty());
+    let report = machine.update(&mut instance, args(1.), &mut sprite);
+    assert_eq!(report.transitions.len(), 1);
+    assert_eq!(instance.current_id, "idle".into());
+    assert_eq!(sprite.0, 10);
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::{args, id, Machine, TestSprite};
+use dynastes::state_machine::{AlreadyPlaying, AnimationState, TransitionReason};
+
+fn machine() -> Machine {
+    Machine::builder()
+        .state("walk", |s| s.frames(0..=3).mspf(100))
+        .state("attack", |s| s.frames(10..=12).mspf(100).then("recover"))
+        .state("recover", |s| s.frames(20..=21).mspf(100).then("walk"))
+        .group("combat", "attack", ["attack"])
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn queued_states_are_played_at_the_end_of_a_loop() {
+    let machine = machine();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    machine
+        .play_queued(&mut instance, id("combat"), AlreadyPlaying::Restart)
+        .unwrap();
+    // Groups are resolved to their default state when queued
+    assert_eq!(instance.queued, Some(id("attack")));
+    let report = machine.update(&mut instance, args(299.), &mut sprite);
+    assert!(report.transitions.is_empty());
+    let report = machine.update(&mut instance, args(1.), &mut sprite);
+    assert_eq!(report.transitions.len(), 1);
+    assert_eq!(report.transitions[0].reason, TransitionReason::Queued);
+    assert_eq!(instance.current_id, id("attack"));
+    assert_eq!(instance.queued, None);
+
+    // Then the states move on by themselves
+    let report = machine.update(&mut instance, args(400.), &mut sprite);
+    let entered: Vec<_> = report
+        .transitions
+        .iter()
+        .map(|t| machine.id_at(t.to).unwrap())
+        .collect();
+    assert_eq!(entered, [&id("recover"), &id("walk")]);
+    // 200ms of attacking and 100ms of recovering leave 100ms of walking
+    assert_eq!(sprite.0, 1);
+}
+
+#[test]
+fn queued_states_set_by_hand_are_still_found() {
+    let machine = machine();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    machine
+        .play_queued(&mut instance, id("attack"), AlreadyPlaying::Restart)
+        .unwrap();
+    instance.queued = Some(id("recover"));
+    machine.update(&mut instance, args(300.), &mut sprite);
+    assert_eq!(instance.current_id, id("recover"));
+
+    instance.queued = Some(id("combat"));
+    machine.update(&mut instance, args(100.), &mut sprite);
+    assert_eq!(instance.current_id, id("attack"));
+}
+
+#[test]
+fn next_states_are_positions_in_the_targets() {
+    let machine = machine();
+    let attack = machine.get_state(&id("attack")).unwrap();
+    let mut data = attack.start();
+    let mut sprite = TestSprite::default();
+
+    assert_eq!(attack.targets(), [id("recover")]);
+    assert_eq!(attack.next_state(&data), None);
+    attack.update(&mut data, args(200.), &mut sprite, &mut Vec::new());
+    assert_eq!(attack.next_state(&data), Some(0));
+}
@@ -0,0 +1,215 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::{id, Instance, Machine, TestSprite};
+use dynastes::{
+    error::ValidationError,
+    state_machine::{AnimationState, Checksum, SeededRng, StableHash, StateID},
+    states::{
+        index::{IndexStateBuilder, PlaybackMode},
+        random_choice::RandomChoiceState,
+    },
+};
+
+/// An idle that picks one of three variations at random every 100ms
+fn idle_variations() -> Machine {
+    // A one-shot ends when it reaches its last frame, so two frames play for 100ms
+    let variation = |index: usize| {
+        move |s: IndexStateBuilder<TestSprite>| {
+            s.frames(index..=index + 1)
+                .mspf(100)
+                .mode(PlaybackMode::Once)
+                .then("choose")
+        }
+    };
+    Machine::builder()
+        .add_state(
+            "choose",
+            RandomChoiceState::new(vec![
+                (id("a"), 1.),
+                (id("b"), 2.),
+                (id("c"), 1.),
+                (id("never"), 0.),
+            ]),
+        )
+        .state("a", variation(10))
+        .state("b", variation(20))
+        .state("c", variation(30))
+        .state("never", variation(40))
+        .build()
+        .unwrap()
+}
+
+/// The variations picked over `count` 100ms updates of `instance`
+fn picks(machine: &Machine, instance: &mut Instance, count: usize) -> Vec<StateID> {
+    let mut sprite = TestSprite::default();
+    let report = common::run(machine, instance, &mut sprite, 100., count);
+    report
+        .transitions
+        .into_iter()
+        .filter(|transition| machine.id_at(transition.from) == Some(&id("choose")))
+        .map(|transition| machine.id_at(transition.to).unwrap().clone())
+        .collect()
+}
+
+#[test]
+fn seeded_instances_pick_the_same_sequence() {
+    let machine = idle_variations();
+    let mut first = machine.try_default_instance_seeded(7).unwrap();
+    let mut second = machine.try_default_instance_seeded(7).unwrap();
+
+    let first_picks = picks(&machine, &mut first, 50);
+    // One pick when the instance starts, then one every time a variation ends
+    assert_eq!(first_picks.len(), 51);
+    assert_eq!(first_picks, picks(&machine, &mut second, 50));
+    assert_eq!(first.checksum(), second.checksum());
+}
+
+#[test]
+fn different_seeds_pick_different_sequences() {
+    let machine = idle_variations();
+    let mut first = machine.try_default_instance_seeded(7).unwrap();
+    let mut second = machine.try_default_instance_seeded(8).unwrap();
+
+    assert_ne!(
+        picks(&machine, &mut first, 50),
+        picks(&machine, &mut second, 50)
+    );
+}
+
+#[test]
+fn cloned_instances_continue_the_same_sequence() {
+    let machine = idle_variations();
+    let mut first = machine.try_default_instance_seeded(7).unwrap();
+    picks(&machine, &mut first, 10);
+    let mut second = first.clone();
+
+    assert_eq!(
+        picks(&machine, &mut first, 50),
+        picks(&machine, &mut second, 50)
+    );
+}
+
+#[test]
+fn choices_follow_their_weights() {
+    let machine = idle_variations();
+    let mut instance = machine.try_default_instance_seeded(1).unwrap();
+    let picks = picks(&machine, &mut instance, 4000);
+    let count = |name: &str| picks.iter().filter(|pick| **pick == id(name)).count();
+
+    assert_eq!(count("never"), 0);
+    // "b" is twice as likely as "a" and "c"
+    assert!((1800..2200).contains(&count("b")), "{}", count("b"));
+    assert!((800..1200).contains(&count("a")), "{}", count("a"));
+    assert!((800..1200).contains(&count("c")), "{}", count("c"));
+}
+
+#[test]
+fn seeded_rng_is_splitmix64() {
+    let mut rng = SeededRng::new(0);
+    assert_eq!(rng.next_u64(), 0xE220_A839_7B1D_CDAF);
+    assert_eq!(rng.next_u64(), 0x6E78_9E6A_A1B9_65F4);
+
+    let mut first = SeededRng::new(42);
+    let mut second = SeededRng::new(42);
+    for _ in 0..100 {
+        let value = first.range_f64(2., 5.);
+        assert!((2. ..5.).contains(&value));
+        assert_eq!(value.to_bits(), second.range_f64(2., 5.).to_bits());
+    }
+}
+
+#[test]
+fn empty_ranges_return_their_minimum() {
+    let mut rng = SeededRng::new(3);
+    let before = rng.clone();
+    assert_eq!(rng.range_f64(4., 4.), 4.);
+    assert_eq!(rng.range_f64(4., 1.), 4.);
+    assert_eq!(rng, before);
+}
+
+#[test]
+fn checksums_are_fnv_1a() {
+    assert_eq!(Checksum::new().finish(), 0xCBF2_9CE4_8422_2325);
+
+    let mut checksum = Checksum::new();
+    checksum.write(b"a");
+    assert_eq!(checksum.finish(), 0xAF63_DC4C_8601_EC8C);
+
+    let mut checksum = Checksum::new();
+    checksum.write(b"foobar");
+    assert_eq!(checksum.finish(), 0x8594_4171_F739_67E8);
+}
+
+#[test]
+fn checksums_are_stable() {
+    // Changing these values breaks the comparison of checksums between versions of the crate
+    assert_eq!(0_u64.checksum(), 0xA8C7_F832_281A_39C5);
+    assert_eq!("idle".checksum(), 0xC44A_1E86_8458_B397);
+    assert_eq!(
+        (Some(1.5_f64), vec![true, false]).checksum(),
+        0x8A2A_B5B2_CBDC_1AAA
+    );
+}
+
+#[test]
+fn checksums_of_maps_ignore_insertion_order() {
+    let mut first = HashMap::new();
+    let mut second = HashMap::new();
+    for i in 0..20_u64 {
+        first.insert(format!("key {i}"), i);
+        second.insert(format!("key {}", 19 - i), 19 - i);
+    }
+    assert_eq!(first.checksum(), second.checksum());
+
+    second.insert("key 0".to_string(), 1);
+    assert_ne!(first.checksum(), second.checksum());
+}
+
+#[test]
+fn instance_checksums_follow_their_state() {
+    let machine = idle_variations();
+    let mut first = machine.try_default_instance_seeded(7).unwrap();
+    let second = machine.try_default_instance_seeded(7).unwrap();
+    assert_eq!(first.checksum(), second.checksum());
+
+    first.parameters.set_bool("hurt", true);
+    assert_ne!(first.checksum(), second.checksum());
+}
+
+#[test]
+fn negative_infinite_and_nan_weights_fail_validation() {
+    let state = RandomChoiceState::<TestSprite>::new(vec![
+        (id("a"), 1.),
+        (id("b"), -1.),
+        (id("c"), f64::INFINITY),
+        (id("d"), f64::NAN),
+        (id("e"), 0.),
+    ]);
+    let invalid = |position| ValidationError::InvalidWeight {
+        state: id("choose"),
+        position,
+    };
+    assert_eq!(
+        state.validate(&id("choose"), None),
+        [invalid(1), invalid(2), invalid(3)]
+    );
+
+    // None of them can be picked either
+    let state = RandomChoiceState::<TestSprite>::new(vec![
+        (id("b"), -1.),
+        (id("c"), f64::INFINITY),
+        (id("d"), f64::NAN),
+    ]);
+    assert_eq!(
+        state.validate(&id("choose"), None),
+        [
+            invalid(0),
+            invalid(1),
+            invalid(2),
+            ValidationError::NoChoices(id("choose"))
+        ]
+    );
+    assert_eq!(state.start_with_rng(&mut SeededRng::new(0)).choice, None);
+}
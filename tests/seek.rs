@@ -0,0 +1,255 @@
+mod common;
+
+use common::{args, id, Machine, TestSprite};
+use dynastes::state_machine::AnimationState;
+use dynastes::states::index::PlaybackMode;
+
+fn one_shot() -> Machine {
+    Machine::builder()
+        .state("attack", |s| {
+            s.frames(0..=9).mspf(100).mode(PlaybackMode::Once)
+        })
+        .build()
+        .unwrap()
+}
+
+fn spin_three_times() -> Machine {
+    Machine::builder()
+        .state("spin", |s| s.frames(0..=3).mspf(100).loops(3).then("idle"))
+        .state("idle", |s| s.frames(10..=11).mspf(100))
+        .build()
+        .unwrap()
+}
+
+/// Checks that `normalized_time` is the elapsed time over the total duration every 50ms until the end
+fn assert_normalized_over_total(machine: &Machine) {
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+    let total_ms = machine.total_duration_ms(&instance).unwrap();
+    let mut elapsed_ms = 0.;
+    while elapsed_ms < total_ms {
+        let expected = elapsed_ms / total_ms;
+        let normalized_time = machine.normalized_time(&instance).unwrap();
+        assert!(
+            (normalized_time - expected).abs() < 1e-9,
+            "{normalized_time} at {elapsed_ms}ms"
+        );
+        machine.update(&mut instance, args(50.), &mut sprite);
+        elapsed_ms += 50.;
+    }
+}
+
+#[test]
+fn one_shots_reach_1_when_they_end() {
+    let machine = one_shot();
+    assert_normalized_over_total(&machine);
+
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+    assert_eq!(machine.total_duration_ms(&instance), Some(900.));
+    machine.update(&mut instance, args(900.), &mut sprite);
+    assert_eq!(machine.normalized_time(&instance), Some(1.));
+    assert_eq!(sprite.0, 9);
+}
+
+#[test]
+fn setting_the_normalized_time_of_a_one_shot() {
+    let machine = one_shot();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    machine
+        .set_normalized_time(&mut instance, 0.5, &mut sprite)
+        .unwrap();
+    assert_eq!(sprite.0, 4);
+    assert_eq!(machine.elapsed_ms(&instance), Some(450.));
+    machine
+        .set_normalized_time(&mut instance, 1., &mut sprite)
+        .unwrap();
+    assert_eq!(sprite.0, 9);
+    assert_eq!(machine.remaining_ms(&instance), Some(0.));
+}
+
+#[test]
+fn repeated_loops_are_normalized_over_every_loop() {
+    let machine = spin_three_times();
+    assert_normalized_over_total(&machine);
+
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+    // The last frame is reached at 300ms, then twice more every 400ms
+    assert_eq!(machine.total_duration_ms(&instance), Some(1100.));
+    machine
+        .set_normalized_time(&mut instance, 0.5, &mut sprite)
+        .unwrap();
+    assert_eq!(machine.elapsed_ms(&instance), Some(550.));
+    assert_eq!(machine.remaining_ms(&instance), Some(550.));
+    assert_eq!(sprite.0, 1);
+
+    // The second and third loops are still to be played
+    machine.update(&mut instance, args(549.), &mut sprite);
+    assert_eq!(instance.current_id, "spin".into());
+    machine.update(&mut instance, args(1.), &mut sprite);
+    assert_eq!(instance.current_id, id("idle"));
+}
+
+#[test]
+fn endless_loops_are_normalized_per_loop() {
+    let machine = Machine::builder()
+        .state("idle", |s| s.frames(0..=3).mspf(100))
+        .build()
+        .unwrap();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    assert_eq!(machine.total_duration_ms(&instance), None);
+    machine.update(&mut instance, args(500.), &mut sprite);
+    assert_eq!(machine.normalized_time(&instance), Some(0.25));
+    machine
+        .set_normalized_time(&mut instance, 0.5, &mut sprite)
+        .unwrap();
+    // Still in the second loop
+    assert_eq!(machine.elapsed_ms(&instance), Some(600.));
+    assert_eq!(sprite.0, 2);
+}
+
+#[test]
+fn frame_lists_are_normalized_over_their_frames() {
+    let machine = Machine::builder()
+        .frame_list("attack", |s| s.frame(0, 100).frame(1, 300).then("idle"))
+        .state("idle", |s| s.frames(10..=11).mspf(100))
+        .build()
+        .unwrap();
+    assert_normalized_over_total(&machine);
+
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+    assert_eq!(machine.total_duration_ms(&instance), Some(400.));
+    machine
+        .set_normalized_time(&mut instance, 0.5, &mut sprite)
+        .unwrap();
+    assert_eq!(sprite.0, 1);
+    assert_eq!(machine.elapsed_ms(&instance), Some(200.));
+}
+
+/// The ms after which the machine leaves its default state, in 1ms updates
+fn first_transition_ms(machine: &Machine) -> usize {
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+    let start = instance.current_id.clone();
+    (1..=10_000)
+        .find(|_| {
+            machine.update(&mut instance, args(1.), &mut sprite);
+            instance.current_id != start
+        })
+        .expect("the state never ended")
+}
+
+#[test]
+fn durations_are_the_total_duration() {
+    for (mode, loops) in [
+        (PlaybackMode::Forward, 1),
+        (PlaybackMode::Forward, 3),
+        (PlaybackMode::Reverse, 2),
+        (PlaybackMode::PingPong, 2),
+        (PlaybackMode::Once, 1),
+    ] {
+        let machine = Machine::builder()
+            .state("attack", |s| {
+                s.frames(0..=9)
+                    .duration(1000)
+                    .mode(mode)
+                    .loops(loops)
+                    .then("idle")
+            })
+            .state("idle", |s| s.frames(10..=11).mspf(100))
+            .build()
+            .unwrap();
+        let attack = machine.get_state(&"attack".into()).unwrap();
+        let instance = machine.default_instance();
+
+        let total_ms = machine.total_duration_ms(&instance).unwrap();
+        assert!(
+            (total_ms - 1000.).abs() < 1e-9,
+            "{mode:?} {loops}: {total_ms}"
+        );
+        assert_eq!(attack.length_ms(), Some(total_ms));
+        assert_eq!(first_transition_ms(&machine), 1000, "{mode:?} {loops}");
+    }
+}
+
+#[test]
+fn durations_of_one_shots_end_on_the_last_frame() {
+    let machine = Machine::builder()
+        .state("attack", |s| {
+            s.frames(0..=9).duration(900).mode(PlaybackMode::Once)
+        })
+        .build()
+        .unwrap();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    assert_eq!(machine.total_duration_ms(&instance), Some(900.));
+    machine.update(&mut instance, args(899.), &mut sprite);
+    assert_eq!(sprite.0, 8);
+    machine.update(&mut instance, args(1.), &mut sprite);
+    assert_eq!(sprite.0, 9);
+}
+
+#[test]
+fn durations_of_endless_loops_are_a_single_loop() {
+    let machine = Machine::builder()
+        .state("idle", |s| s.frames(0..=3).duration(800).loops(3))
+        .build()
+        .unwrap();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    assert_eq!(machine.total_duration_ms(&instance), None);
+    assert_eq!(
+        machine.get_state(&id("idle")).unwrap().length_ms(),
+        Some(800.)
+    );
+    // Every frame is shown for 200ms
+    machine.update(&mut instance, args(1000.), &mut sprite);
+    assert_eq!(sprite.0, 1);
+}
+
+#[test]
+fn huge_and_non_finite_positions_do_not_overflow() {
+    let machine = Machine::builder()
+        .state("idle", |s| {
+            s.frames(0..=3).mspf(100).mode(PlaybackMode::PingPong)
+        })
+        .state("spin", |s| {
+            s.frames(10..=13).mspf(100).loops(3).then("idle")
+        })
+        .build()
+        .unwrap();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    machine.seek(&mut instance, 1e30, &mut sprite).unwrap();
+    assert!(machine.normalized_time(&instance).unwrap().is_finite());
+    machine.update(&mut instance, args(1e30), &mut sprite);
+    assert_eq!(instance.current_id, id("idle"));
+
+    // Non-finite positions leave the playback where it is
+    machine.seek(&mut instance, 150., &mut sprite).unwrap();
+    for ms in [f64::INFINITY, f64::NEG_INFINITY, f64::NAN] {
+        machine.seek(&mut instance, ms, &mut sprite).unwrap();
+        machine
+            .set_normalized_time(&mut instance, ms, &mut sprite)
+            .unwrap();
+        machine
+            .sync_to_clock(&mut instance, ms, &mut sprite)
+            .unwrap();
+        assert_eq!(machine.elapsed_ms(&instance), Some(150.));
+        assert_eq!(sprite.0, 1);
+    }
+
+    // Loops that end move on after a huge update
+    let mut instance = machine.try_new_instance(id("spin")).unwrap();
+    machine.update(&mut instance, args(1e30), &mut sprite);
+    assert_eq!(instance.current_id, id("idle"));
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::{args, id, run, Machine, TestSprite};
+use dynastes::state_machine::Marker;
+
+fn machine() -> Machine {
+    Machine::builder()
+        .state("attack", |s| {
+            s.frames(0..=3)
+                .mspf(100)
+                .marker(Marker::frame("hit", 2))
+                .then("idle")
+        })
+        .state("idle", |s| s.frames(10..=11).mspf(100))
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn paused_instances_do_not_play() {
+    let machine = machine();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    instance.pause();
+    assert!(instance.is_paused());
+    let report = run(&machine, &mut instance, &mut sprite, 100., 10);
+    assert!(report.is_empty());
+    assert_eq!(instance.current_id, id("attack"));
+    assert_eq!(machine.elapsed_ms(&instance), Some(0.));
+
+    instance.resume();
+    let report = machine.update(&mut instance, args(1000.), &mut sprite);
+    assert_eq!(report.markers.len(), 1);
+    assert_eq!(report.transitions.len(), 1);
+}
+
+#[test]
+fn speeds_scale_the_delta() {
+    let machine = machine();
+    let mut fast = machine.default_instance();
+    let mut fast_sprite = TestSprite::default();
+    fast.set_speed(2.);
+    let mut normal = machine.default_instance();
+    let mut normal_sprite = TestSprite::default();
+
+    for _ in 0..20 {
+        let fast_report = machine.update(&mut fast, args(15.), &mut fast_sprite);
+        let normal_report = machine.update(&mut normal, args(30.), &mut normal_sprite);
+        assert_eq!(fast_report, normal_report);
+        assert_eq!(fast.current_id, normal.current_id);
+        assert_eq!(fast_sprite, normal_sprite);
+        assert_eq!(machine.elapsed_ms(&fast), machine.elapsed_ms(&normal));
+    }
+    assert_eq!(fast.current_id, id("idle"));
+}
+
+#[test]
+fn negative_speeds_are_treated_as_0() {
+    let machine = machine();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    instance.set_speed(-1.);
+    assert_eq!(instance.speed(), 0.);
+    let report = machine.update(&mut instance, args(1000.), &mut sprite);
+    assert!(report.is_empty());
+    assert_eq!(machine.elapsed_ms(&instance), Some(0.));
+}
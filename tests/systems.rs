@@ -0,0 +1,488 @@
+use std::time::{Duration, Instant};
+
+use bevy::{
+    asset::AssetPlugin,
+    core::TaskPoolPlugin,
+    ecs::event::{Events, ManualEventReader},
+    prelude::{App, Assets, Entity, Handle, Visibility},
+    sprite::{TextureAtlas, TextureAtlasSprite},
+    time::Time,
+};
+use dynastes::{
+    bevy::{
+        AnimationCommand, AnimationLayer, AnimationMarker, AnimationSnapshot, AnimationTimeScale,
+        AnimationUpdateMode, BevyASM, CrossfadeOverlay, MaybeBevyStateInstance,
+        SpriteAnimationPlugin, StateChanged, SyncGroupMember,
+    },
+    state_machine::{
+        AlreadyPlaying, AnimationStateMachine, Condition, LayerMode, LayerOptions, Marker,
+        StableHash, StateID, Transition, TransitionReason,
+    },
+    states::index::PlaybackMode,
+};
+
+/// What happened to every entity over the frames of a run
+#[derive(Debug, PartialEq)]
+struct Run {
+    sprites: Vec<usize>,
+    markers: Vec<(Entity, String)>,
+    state_changes: Vec<(Entity, String)>,
+}
+
+fn walk() -> BevyASM {
+    BevyASM(
+        AnimationStateMachine::builder()
+            .state("walk", |s| {
+                s.frames(0..=7).mspf(70).marker(Marker::frame("step", 3))
+            })
+            .build()
+            .unwrap(),
+    )
+}
+
+fn attack() -> BevyASM {
+    BevyASM(
+        AnimationStateMachine::builder()
+            .state("attack", |s| {
+                s.frames(10..=15)
+                    .mspf(45)
+                    .mode(PlaybackMode::Once)
+                    .then("recover")
+            })
+            .state("recover", |s| {
+                s.frames(20..=22)
+                    .mspf(60)
+                    .loops(2)
+                    .marker(Marker::time("breath", 30.))
+                    .then("attack")
+            })
+            .build()
+            .unwrap(),
+    )
+}
+
+/// An app running the `SpriteAnimationPlugin` in `mode`, whose time is advanced by hand
+fn app(mode: AnimationUpdateMode) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        TaskPoolPlugin::default(),
+        AssetPlugin::default(),
+        SpriteAnimationPlugin,
+    ));
+    // Advanced by hand, so every run sees the same deltas
+    app.init_resource::<Time>();
+    app.insert_resource(mode);
+    app
+}
+
+/// Runs 300 entities split between two ASMs and a sync group for 20 frames of 55ms in `mode`
+fn run(mode: AnimationUpdateMode) -> Run {
+    let mut app = app(mode);
+
+    let mut asms = app.world.resource_mut::<Assets<BevyASM>>();
+    let handles: [Handle<BevyASM>; 2] = [asms.add(walk()), asms.add(attack())];
+    let entities: Vec<Entity> = (0..300)
+        .map(|i| {
+            let mut entity = app.world.spawn((
+                handles[i % 2].clone(),
+                TextureAtlasSprite::default(),
+                MaybeBevyStateInstance::default(),
+            ));
+            if i % 10 == 0 {
+                entity.insert(SyncGroupMember::new("crowd"));
+            }
+            entity.id()
+        })
+        .collect();
+
+    let mut marker_reader = ManualEventReader::<AnimationMarker>::default();
+    let mut state_change_reader = ManualEventReader::<StateChanged>::default();
+    let mut markers = Vec::new();
+    let mut state_changes = Vec::new();
+    let start = Instant::now();
+    for frame in 0..20 {
+        app.world
+            .resource_mut::<Time>()
+            .update_with_instant(start + Duration::from_millis(55 * frame));
+        app.update();
+        markers.extend(
+            marker_reader
+                .iter(app.world.resource::<Events<AnimationMarker>>())
+                .map(|event| (event.entity, event.name.clone())),
+        );
+        state_changes.extend(
+            state_change_reader
+                .iter(app.world.resource::<Events<StateChanged>>())
+                .map(|event| (event.entity, event.to.0.clone())),
+        );
+    }
+
+    Run {
+        sprites: entities
+            .iter()
+            .map(|entity| app.world.get::<TextureAtlasSprite>(*entity).unwrap().index)
+            .collect(),
+        markers,
+        state_changes,
+    }
+}
+
+#[test]
+fn parallel_updates_match_serial_updates() {
+    let serial = run(AnimationUpdateMode::Serial);
+    assert!(!serial.markers.is_empty());
+    assert!(!serial.state_changes.is_empty());
+    assert_eq!(serial, run(AnimationUpdateMode::Parallel));
+}
+
+#[test]
+fn sync_group_members_only_follow_their_group() {
+    for mode in [AnimationUpdateMode::Serial, AnimationUpdateMode::Parallel] {
+        let run = run(mode);
+        // Members show the frame at the group's clock, and don't play their states themselves
+        let members: Vec<_> = run.sprites.iter().step_by(10).collect();
+        assert!(members.windows(2).all(|pair| pair[0] == pair[1]));
+        let member_index = |entity: &Entity| entity.index().is_multiple_of(10);
+        assert!(!run.markers.iter().any(|(entity, _)| member_index(entity)));
+        assert!(!run
+            .state_changes
+            .iter()
+            .any(|(entity, _)| member_index(entity)));
+    }
+}
+
+/// An app with a single entity animated by the `attack` ASM, and the entity
+fn command_app() -> (App, Entity) {
+    let mut app = app(AnimationUpdateMode::Serial);
+    let handle = app.world.resource_mut::<Assets<BevyASM>>().add(attack());
+    let entity = app
+        .world
+        .spawn((
+            handle,
+            TextureAtlasSprite::default(),
+            MaybeBevyStateInstance::default(),
+        ))
+        .id();
+    app.update();
+    (app, entity)
+}
+
+fn current_id(app: &App, entity: Entity) -> StateID {
+    let instance = app.world.get::<MaybeBevyStateInstance>(entity).unwrap();
+    instance.0.as_ref().unwrap().0.current_id.clone()
+}
+
+/// The state changes sent since the last time `reader` was read
+fn state_changes(
+    app: &App,
+    reader: &mut ManualEventReader<StateChanged>,
+) -> Vec<(String, String, TransitionReason)> {
+    reader
+        .iter(app.world.resource::<Events<StateChanged>>())
+        .map(|event| (event.from.0.clone(), event.to.0.clone(), event.reason))
+        .collect()
+}
+
+#[test]
+fn play_commands_change_the_state() {
+    let (mut app, entity) = command_app();
+    let mut reader = ManualEventReader::<StateChanged>::default();
+
+    app.world.send_event(AnimationCommand::Play {
+        entity,
+        state: "recover".into(),
+        already_playing: AlreadyPlaying::Continue,
+    });
+    app.update();
+    assert_eq!(current_id(&app, entity), "recover".into());
+    assert_eq!(
+        state_changes(&app, &mut reader),
+        [(
+            "attack".to_string(),
+            "recover".to_string(),
+            TransitionReason::Played
+        )]
+    );
+
+    // Already playing, so nothing changes
+    app.world.send_event(AnimationCommand::Play {
+        entity,
+        state: "recover".into(),
+        already_playing: AlreadyPlaying::Continue,
+    });
+    app.update();
+    assert!(state_changes(&app, &mut reader).is_empty());
+}
+
+#[test]
+fn play_queued_commands_wait_for_the_end_of_the_state() {
+    let (mut app, entity) = command_app();
+    let mut reader = ManualEventReader::<StateChanged>::default();
+
+    app.world.send_event(AnimationCommand::PlayQueued {
+        entity,
+        state: "recover".into(),
+        already_playing: AlreadyPlaying::Restart,
+    });
+    app.update();
+    assert_eq!(current_id(&app, entity), "attack".into());
+    assert!(state_changes(&app, &mut reader).is_empty());
+
+    // Attacking takes 5 * 45ms
+    let start = Instant::now();
+    app.world.resource_mut::<Time>().update_with_instant(start);
+    app.world
+        .resource_mut::<Time>()
+        .update_with_instant(start + Duration::from_millis(230));
+    app.update();
+    assert_eq!(
+        state_changes(&app, &mut reader),
+        [(
+            "attack".to_string(),
+            "recover".to_string(),
+            TransitionReason::Queued
+        )]
+    );
+}
+
+#[test]
+fn commands_to_entities_without_an_animation_are_ignored() {
+    let (mut app, entity) = command_app();
+    let other = app.world.spawn(TextureAtlasSprite::default()).id();
+    app.world.send_event(AnimationCommand::Play {
+        entity: other,
+        state: "recover".into(),
+        already_playing: AlreadyPlaying::Restart,
+    });
+    app.world.send_event(AnimationCommand::Play {
+        entity,
+        state: "missing".into(),
+        already_playing: AlreadyPlaying::Restart,
+    });
+    app.update();
+    assert_eq!(current_id(&app, entity), "attack".into());
+}
+
+#[test]
+fn negative_and_non_finite_time_scales_freeze_animations() {
+    for scale in [-1., f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+        assert_eq!(AnimationTimeScale(scale).factor(), 0.);
+
+        let (mut app, entity) = command_app();
+        app.insert_resource(AnimationTimeScale(scale));
+        let start = Instant::now();
+        app.world.resource_mut::<Time>().update_with_instant(start);
+        app.world
+            .resource_mut::<Time>()
+            .update_with_instant(start + Duration::from_millis(500));
+        app.update();
+
+        // At normal speed, attacking would be over
+        assert_eq!(current_id(&app, entity), "attack".into(), "{scale}");
+        assert_eq!(
+            app.world.get::<TextureAtlasSprite>(entity).unwrap().index,
+            10,
+            "{scale}"
+        );
+    }
+    assert_eq!(AnimationTimeScale(0.5).factor(), 0.5);
+}
+
+#[test]
+fn crossfade_overlays_show_the_old_state_until_the_fade_ends() {
+    let mut app = app(AnimationUpdateMode::Serial);
+    let asm = BevyASM(
+        AnimationStateMachine::builder()
+            .state("walk", |s| s.frames(0..=3).mspf(100))
+            .state("run", |s| s.frames(10..=13).mspf(100))
+            .transition(
+                Transition::new("walk".into(), "run".into())
+                    .when(Condition::Trigger("run".to_string()))
+                    .with_crossfade(200.),
+            )
+            .build()
+            .unwrap(),
+    );
+    let handle = app.world.resource_mut::<Assets<BevyASM>>().add(asm);
+    let target = app
+        .world
+        .spawn((
+            handle,
+            TextureAtlasSprite::default(),
+            Handle::<TextureAtlas>::default(),
+            MaybeBevyStateInstance::default(),
+        ))
+        .id();
+    let overlay = app
+        .world
+        .spawn((
+            CrossfadeOverlay { target },
+            TextureAtlasSprite::default(),
+            Handle::<TextureAtlas>::default(),
+            Visibility::Inherited,
+        ))
+        .id();
+    let start = Instant::now();
+    app.world.resource_mut::<Time>().update_with_instant(start);
+    app.update();
+    assert_eq!(
+        app.world.get::<Visibility>(overlay),
+        Some(&Visibility::Hidden)
+    );
+
+    let mut instance = app.world.get_mut::<MaybeBevyStateInstance>(target).unwrap();
+    instance.0.as_mut().unwrap().0.parameters.set_trigger("run");
+    // (ms since the start, alpha of the overlay), the fade starts 150ms in on frame 1 of "walk"
+    for (ms, alpha) in [(150, Some(1.)), (250, Some(0.5)), (350, None)] {
+        app.world
+            .resource_mut::<Time>()
+            .update_with_instant(start + Duration::from_millis(ms));
+        app.update();
+        let visibility = app.world.get::<Visibility>(overlay).unwrap();
+        let sprite = app.world.get::<TextureAtlasSprite>(overlay).unwrap();
+        match alpha {
+            Some(alpha) => {
+                assert_eq!(*visibility, Visibility::Inherited, "{ms}");
+                assert_eq!(sprite.color.a(), alpha, "{ms}");
+                assert_eq!(sprite.index, 1 + (ms as usize - 150) / 100, "{ms}");
+            }
+            None => assert_eq!(*visibility, Visibility::Hidden, "{ms}"),
+        }
+    }
+}
+
+#[test]
+fn layers_follow_their_base_entity() {
+    let (mut app, base) = command_app();
+    let arms = BevyASM(
+        AnimationStateMachine::builder()
+            .state("idle", |s| s.frames(70..=71).mspf(100))
+            .state("recover", |s| s.frames(80..=82).mspf(60))
+            .build()
+            .unwrap(),
+    );
+    let handle = app.world.resource_mut::<Assets<BevyASM>>().add(arms);
+    let layer = app
+        .world
+        .spawn((
+            AnimationLayer {
+                base,
+                options: LayerOptions {
+                    mode: LayerMode::Follow,
+                    sync_time: true,
+                },
+            },
+            handle,
+            TextureAtlasSprite::default(),
+            MaybeBevyStateInstance::default(),
+        ))
+        .id();
+    let mut reader = ManualEventReader::<StateChanged>::default();
+
+    // The base attacks for 225ms, which the arms can't follow
+    let start = Instant::now();
+    for (ms, base_state, layer_state) in [
+        (0, "attack", "idle"),
+        (200, "attack", "idle"),
+        (260, "recover", "recover"),
+    ] {
+        app.world
+            .resource_mut::<Time>()
+            .update_with_instant(start + Duration::from_millis(ms));
+        app.update();
+        assert_eq!(current_id(&app, base), base_state.into(), "{ms}");
+        assert_eq!(current_id(&app, layer), layer_state.into(), "{ms}");
+    }
+    let followed: Vec<_> = reader
+        .iter(app.world.resource::<Events<StateChanged>>())
+        .filter(|event| event.entity == layer)
+        .map(|event| event.reason)
+        .collect();
+    assert_eq!(followed, [TransitionReason::Followed]);
+    // Both are 35ms into recovering, synced to the base's frame
+    assert_eq!(
+        app.world.get::<TextureAtlasSprite>(layer).unwrap().index,
+        80
+    );
+}
+
+/// Runs `frames` more frames of 55ms, going on from `frame`
+fn advance(app: &mut App, start: Instant, frame: &mut u64, frames: u64) {
+    for _ in 0..frames {
+        *frame += 1;
+        app.world
+            .resource_mut::<Time>()
+            .update_with_instant(start + Duration::from_millis(55 * *frame));
+        app.update();
+    }
+}
+
+#[test]
+fn restored_snapshots_resimulate_the_same_animations() {
+    let (mut app, entity) = command_app();
+    let member = app
+        .world
+        .spawn((
+            app.world.get::<Handle<BevyASM>>(entity).unwrap().clone(),
+            TextureAtlasSprite::default(),
+            MaybeBevyStateInstance::default(),
+            SyncGroupMember::new("crowd").with_random_offset(100.),
+        ))
+        .id();
+    let start = Instant::now();
+    let mut frame = 0;
+    app.world.resource_mut::<Time>().update_with_instant(start);
+    advance(&mut app, start, &mut frame, 3);
+
+    let snapshot = AnimationSnapshot::take(&mut app.world);
+    assert_eq!(snapshot.entities.len(), 2);
+    advance(&mut app, start, &mut frame, 10);
+    let ahead = AnimationSnapshot::take(&mut app.world);
+    assert_ne!(ahead.checksum(), snapshot.checksum());
+
+    // Rolled back, and played again with the same deltas
+    snapshot.restore(&mut app.world);
+    assert_eq!(
+        AnimationSnapshot::take(&mut app.world).checksum(),
+        snapshot.checksum()
+    );
+    advance(&mut app, start, &mut frame, 10);
+    assert_eq!(
+        AnimationSnapshot::take(&mut app.world).checksum(),
+        ahead.checksum()
+    );
+    assert!(app.world.get::<SyncGroupMember>(member).is_some());
+}
+
+#[test]
+fn restoring_a_snapshot_only_touches_its_entities() {
+    let (mut app, entity) = command_app();
+    let snapshot = AnimationSnapshot::take(&mut app.world);
+
+    let later = app
+        .world
+        .spawn((
+            app.world.get::<Handle<BevyASM>>(entity).unwrap().clone(),
+            TextureAtlasSprite::default(),
+            MaybeBevyStateInstance::default(),
+            SyncGroupMember::new("crowd"),
+        ))
+        .id();
+    app.update();
+    let before = app
+        .world
+        .get::<MaybeBevyStateInstance>(later)
+        .unwrap()
+        .0
+        .as_ref()
+        .unwrap()
+        .0
+        .checksum();
+    let member = app.world.get::<SyncGroupMember>(later).cloned();
+
+    snapshot.restore(&mut app.world);
+    let instance = app.world.get::<MaybeBevyStateInstance>(later).unwrap();
+    assert_eq!(instance.0.as_ref().unwrap().0.checksum(), before);
+    assert_eq!(app.world.get::<SyncGroupMember>(later).cloned(), member);
+    assert_eq!(current_id(&app, entity), "attack".into());
+}
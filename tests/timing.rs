@@ -0,0 +1,170 @@
+mod common;
+
+use common::{args, id, run, Machine, TestSprite};
+use dynastes::{
+    state_machine::{Marker, UpdateReport},
+    states::index::PlaybackMode,
+};
+
+/// The names of the markers in `report`, sorted so reports of differently split updates compare equal
+fn sorted_names(report: &UpdateReport) -> Vec<String> {
+    let mut names: Vec<_> = report
+        .markers
+        .iter()
+        .map(|marker| format!("{}/{}", marker.state.0, marker.name))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Checks that one 5000ms update ends up exactly where 5000 updates of 1ms do
+fn assert_delta_independent(machine: &Machine) {
+    let mut once = machine.try_default_instance_seeded(1).unwrap();
+    let mut once_sprite = TestSprite::default();
+    let once_report = machine.update(&mut once, args(5000.), &mut once_sprite);
+
+    let mut split = machine.try_default_instance_seeded(1).unwrap();
+    let mut split_sprite = TestSprite::default();
+    let split_report = run(machine, &mut split, &mut split_sprite, 1., 5000);
+
+    assert_eq!(once.current_id, split.current_id);
+    assert_eq!(once_sprite, split_sprite);
+    assert_eq!(once_report.transitions, split_report.transitions);
+    assert_eq!(sorted_names(&once_report), sorted_names(&split_report));
+    assert_eq!(machine.elapsed_ms(&once), machine.elapsed_ms(&split));
+    assert_eq!(
+        machine.normalized_time(&once),
+        machine.normalized_time(&split)
+    );
+}
+
+#[test]
+fn loops_are_delta_independent() {
+    let machine = Machine::builder()
+        .state("idle", |s| {
+            s.frames(0..=6)
+                .mspf(70)
+                .marker(Marker::frame("blink", 4))
+                .marker(Marker::time("breathe", 35.))
+        })
+        .build()
+        .unwrap();
+    assert_delta_independent(&machine);
+}
+
+#[test]
+fn ping_pongs_are_delta_independent() {
+    let machine = Machine::builder()
+        .state("sway", |s| {
+            s.frames(0..=4)
+                .mspf(90)
+                .mode(PlaybackMode::PingPong)
+                .marker(Marker::frame("edge", 4))
+                .marker(Marker::time("middle", 360.))
+        })
+        .build()
+        .unwrap();
+    assert_delta_independent(&machine);
+}
+
+#[test]
+fn repeated_loops_are_delta_independent() {
+    let machine = Machine::builder()
+        .state("spin", |s| {
+            s.frames(0..=3)
+                .mspf(110)
+                .loops(3)
+                .marker(Marker::frame("turn", 2))
+                .then("land")
+        })
+        .state("land", |s| {
+            s.frames(10..=14)
+                .mspf(60)
+                .mode(PlaybackMode::Once)
+                .marker(Marker::time("thud", 120.))
+                .then("spin")
+        })
+        .build()
+        .unwrap();
+    assert_delta_independent(&machine);
+
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+    // Spinning takes 330 + 2 * 440ms, and landing 240ms
+    let report = machine.update(&mut instance, args(5000.), &mut sprite);
+    assert_eq!(report.transitions.len(), 6);
+    assert_eq!(instance.current_id, id("spin"));
+}
+
+#[test]
+fn frame_list_chains_are_delta_independent() {
+    let machine = Machine::builder()
+        .frame_list("wind_up", |s| {
+            s.frame(0, 120)
+                .frame(1, 45)
+                .marker(Marker::frame("grunt", 1))
+                .then("swing")
+        })
+        .frame_list("swing", |s| {
+            s.frame(2, 30)
+                .frame(3, 30)
+                .frame(4, 95)
+                .marker(Marker::time("whoosh", 40.))
+                .then("recover")
+        })
+        .state("recover", |s| {
+            s.frames(5..=7)
+                .mspf(80)
+                .mode(PlaybackMode::Reverse)
+                .loops(2)
+                .then("wind_up")
+        })
+        .build()
+        .unwrap();
+    assert_delta_independent(&machine);
+
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+    // A round is 165 + 155 + 160 + 240ms
+    let report = machine.update(&mut instance, args(5000.), &mut sprite);
+    assert_eq!(report.transitions.len(), 20);
+    assert_eq!(instance.current_id, id("recover"));
+    assert_eq!(sprite.0, 6);
+}
+
+#[test]
+fn markers_are_grouped_by_state_with_time_markers_first() {
+    let machine = Machine::builder()
+        .state("attack", |s| {
+            s.frames(0..=3)
+                .mspf(100)
+                .mode(PlaybackMode::Once)
+                .marker(Marker::frame("hit", 1))
+                .marker(Marker::time("swing", 150.))
+                .then("idle")
+        })
+        .state("idle", |s| {
+            s.frames(10..=11)
+                .mspf(100)
+                .marker(Marker::frame("rest", 10))
+        })
+        .build()
+        .unwrap();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    let report = machine.update(&mut instance, args(400.), &mut sprite);
+    let markers: Vec<_> = report
+        .markers
+        .iter()
+        .map(|marker| {
+            let state = machine.id_at(marker.state).unwrap();
+            (state.0.as_str(), marker.name.as_str())
+        })
+        .collect();
+    // "hit" was crossed at 100ms, before "swing" at 150ms
+    assert_eq!(
+        markers,
+        [("attack", "swing"), ("attack", "hit"), ("idle", "rest")]
+    );
+}
@@ -0,0 +1,228 @@
+mod common;
+
+use common::{args, id, Machine, TestSprite};
+use dynastes::state_machine::{
+    Comparison, Condition, Parameters, StableHash, Transition, TransitionReason,
+};
+
+fn float(comparison: Comparison, value: f64) -> Condition {
+    Condition::Float {
+        name: "speed".to_string(),
+        comparison,
+        value,
+    }
+}
+
+fn int(comparison: Comparison, value: i64) -> Condition {
+    Condition::Int {
+        name: "health".to_string(),
+        comparison,
+        value,
+    }
+}
+
+#[test]
+fn every_comparison_holds_where_its_operator_does() {
+    let cases = [
+        (Comparison::Less, [true, false, false]),
+        (Comparison::LessOrEqual, [true, true, false]),
+        (Comparison::Greater, [false, false, true]),
+        (Comparison::GreaterOrEqual, [false, true, true]),
+        (Comparison::Equal, [false, true, false]),
+        (Comparison::NotEqual, [true, false, true]),
+    ];
+    let mut parameters = Parameters::default();
+    for (comparison, expected) in cases {
+        // The parameter below, at and above the condition's value
+        for (value, expected) in [1, 2, 3].into_iter().zip(expected) {
+            parameters.set_float("speed", value as f64);
+            parameters.set_int("health", value);
+            assert_eq!(
+                float(comparison, 2.).is_met(&parameters),
+                expected,
+                "{value} {comparison:?} 2."
+            );
+            assert_eq!(
+                int(comparison, 2).is_met(&parameters),
+                expected,
+                "{value} {comparison:?} 2"
+            );
+        }
+    }
+}
+
+#[test]
+fn bool_conditions_need_the_exact_value() {
+    let grounded = |value| Condition::Bool {
+        name: "grounded".to_string(),
+        value,
+    };
+    let mut parameters = Parameters::default();
+    assert!(!grounded(true).is_met(&parameters));
+    assert!(!grounded(false).is_met(&parameters));
+
+    parameters.set_bool("grounded", true);
+    assert!(grounded(true).is_met(&parameters));
+    assert!(!grounded(false).is_met(&parameters));
+}
+
+#[test]
+fn unset_and_mistyped_parameters_never_meet_a_condition() {
+    let mut parameters = Parameters::default();
+    for comparison in [Comparison::Equal, Comparison::NotEqual] {
+        assert!(!float(comparison, 0.).is_met(&parameters));
+        assert!(!int(comparison, 0).is_met(&parameters));
+    }
+    assert!(!Condition::Trigger("jump".to_string()).is_met(&parameters));
+
+    parameters.set_int("speed", 0);
+    parameters.set_float("health", 0.);
+    parameters.set_bool("jump", true);
+    for comparison in [Comparison::Equal, Comparison::NotEqual] {
+        assert!(!float(comparison, 0.).is_met(&parameters));
+        assert!(!int(comparison, 0).is_met(&parameters));
+    }
+    assert!(!Condition::Trigger("jump".to_string()).is_met(&parameters));
+}
+
+fn jump_machine() -> Machine {
+    Machine::builder()
+        .state("idle", |s| s.frames(0..=3).mspf(100))
+        .state("jump", |s| s.frames(10..=13).mspf(100))
+        .transition(
+            Transition::new(id("idle"), id("jump"))
+                .when(Condition::Trigger("jump".to_string()))
+                .when(Condition::Bool {
+                    name: "grounded".to_string(),
+                    value: true,
+                }),
+        )
+        .transition(
+            Transition::new(id("jump"), id("idle")).when(Condition::Bool {
+                name: "grounded".to_string(),
+                value: true,
+            }),
+        )
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn triggers_are_consumed_by_the_transition_taken() {
+    let machine = jump_machine();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    instance.parameters.set_trigger("jump");
+    instance.parameters.set_bool("grounded", true);
+    let report = machine.update(&mut instance, args(10.), &mut sprite);
+    assert_eq!(report.transitions.len(), 1);
+    assert_eq!(report.transitions[0].reason, TransitionReason::Conditional);
+    assert_eq!(instance.current_id, id("jump"));
+    assert!(!instance.parameters.is_triggered("jump"));
+
+    // Back in "idle", the trigger has to be set again to jump again
+    machine.update(&mut instance, args(10.), &mut sprite);
+    assert_eq!(instance.current_id, id("idle"));
+    machine.update(&mut instance, args(10.), &mut sprite);
+    assert_eq!(instance.current_id, id("idle"));
+}
+
+#[test]
+fn triggers_stay_set_until_a_transition_takes_them() {
+    let machine = jump_machine();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    instance.parameters.set_trigger("jump");
+    instance.parameters.set_bool("grounded", false);
+    let report = machine.update(&mut instance, args(10.), &mut sprite);
+    assert!(report.transitions.is_empty());
+    assert!(instance.parameters.is_triggered("jump"));
+
+    instance.parameters.set_bool("grounded", true);
+    machine.update(&mut instance, args(10.), &mut sprite);
+    assert_eq!(instance.current_id, id("jump"));
+    assert!(!instance.parameters.is_triggered("jump"));
+}
+
+#[test]
+fn transitions_are_checked_in_the_order_they_were_added() {
+    let machine = Machine::builder()
+        .state("idle", |s| s.frames(0..=3).mspf(100))
+        .state("walk", |s| s.frames(10..=13).mspf(100))
+        .state("run", |s| s.frames(20..=23).mspf(100))
+        .transition(Transition::new(id("idle"), id("walk")).when(float(Comparison::Greater, 0.)))
+        .transition(Transition::new(id("idle"), id("run")).when(float(Comparison::Greater, 5.)))
+        .build()
+        .unwrap();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    // Both are met, the first one added wins
+    instance.parameters.set_float("speed", 10.);
+    let report = machine.update(&mut instance, args(10.), &mut sprite);
+    assert_eq!(report.transitions.len(), 1);
+    assert_eq!(instance.current_id, id("walk"));
+}
+
+#[test]
+fn conditional_transitions_take_precedence_over_next_states() {
+    let machine = Machine::builder()
+        .state("attack", |s| s.frames(0..=3).mspf(100).then("idle"))
+        .state("idle", |s| s.frames(10..=11).mspf(100))
+        .state("combo", |s| s.frames(20..=23).mspf(100))
+        .transition(
+            Transition::new(id("attack"), id("combo"))
+                .when(Condition::Trigger("attack".to_string())),
+        )
+        .build()
+        .unwrap();
+    let mut instance = machine.default_instance();
+    let mut sprite = TestSprite::default();
+
+    instance.parameters.set_trigger("attack");
+    let report = machine.update(&mut instance, args(300.), &mut sprite);
+    assert_eq!(report.transitions.len(), 1);
+    assert_eq!(machine.id_at(report.transitions[0].to), Some(&id("combo")));
+}
+
+#[test]
+fn conditions_find_parameters_set_in_any_order() {
+    let machine = Machine::builder()
+        .state("idle", |s| s.frames(0..=3).mspf(100))
+        .state("run", |s| s.frames(10..=13).mspf(100))
+        .transition(
+            Transition::new(id("idle"), id("run"))
+                .when(Condition::Bool {
+                    name: "grounded".to_string(),
+                    value: true,
+                })
+                .when(float(Comparison::Greater, 5.)),
+        )
+        .build()
+        .unwrap();
+    let mut sprite = TestSprite::default();
+
+    // Parameters replaced by hand don't have their slots where the machine expects them
+    let mut instance = machine.default_instance();
+    instance.parameters = Parameters::default();
+    instance.parameters.set_int("health", 3);
+    instance.parameters.set_float("speed", 10.);
+    instance.parameters.set_bool("grounded", true);
+    machine.update(&mut instance, args(10.), &mut sprite);
+    assert_eq!(instance.current_id, id("run"));
+
+    // Checksums don't depend on the order the parameters were set in either
+    let mut reordered = Parameters::default();
+    reordered.set_bool("grounded", true);
+    reordered.set_float("speed", 10.);
+    reordered.set_int("health", 3);
+    assert_eq!(reordered.checksum(), instance.parameters.checksum());
+    let mut fresh = machine.default_instance();
+    fresh.parameters.set_float("speed", 10.);
+    fresh.parameters.set_int("health", 3);
+    fresh.parameters.set_bool("grounded", true);
+    assert_eq!(fresh.parameters.checksum(), instance.parameters.checksum());
+    assert_eq!(fresh.parameters.iter().count(), 3);
+}